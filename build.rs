@@ -1,5 +1,3 @@
-use phf_codegen;
-
 use std::env;
 use std::fs::File;
 use std::io::{BufWriter, Write};
@@ -9,16 +7,16 @@ fn main() {
     let path = Path::new(&env::var("OUT_DIR").unwrap()).join("codegen.rs");
     let mut file = BufWriter::new(File::create(&path).unwrap());
 
-    write!(&mut file, "#[allow(clippy::unreadable_literal)]\n").unwrap();
+    writeln!(&mut file, "#[allow(clippy::unreadable_literal)]").unwrap();
     write!(&mut file, "static PROPERTY_KEYS: phf::Set<&'static str> = ").unwrap();
     phf_codegen::Set::new()
         .entry("properties")
         .entry("patternProperties")
         .build(&mut file)
         .unwrap();
-    write!(&mut file, ";\n").unwrap();
+    writeln!(&mut file, ";").unwrap();
 
-    write!(&mut file, "#[allow(clippy::unreadable_literal)]\n").unwrap();
+    writeln!(&mut file, "#[allow(clippy::unreadable_literal)]").unwrap();
     write!(
         &mut file,
         "static NON_SCHEMA_KEYS: phf::Set<&'static str> = "
@@ -29,14 +27,16 @@ fn main() {
         .entry("patternProperties")
         .entry("dependencies")
         .entry("definitions")
+        .entry("$defs")
+        .entry("dependentSchemas")
         .entry("anyOf")
         .entry("allOf")
         .entry("oneOf")
         .build(&mut file)
         .unwrap();
-    write!(&mut file, ";\n").unwrap();
+    writeln!(&mut file, ";").unwrap();
 
-    write!(&mut file, "#[allow(clippy::unreadable_literal)]\n").unwrap();
+    writeln!(&mut file, "#[allow(clippy::unreadable_literal)]").unwrap();
     write!(
         &mut file,
         "static BOOLEAN_SCHEMA_ARRAY_KEYS: phf::Set<&'static str> = "
@@ -47,21 +47,24 @@ fn main() {
         .entry("anyOf")
         .entry("items")
         .entry("oneOf")
+        .entry("prefixItems")
         .build(&mut file)
         .unwrap();
-    write!(&mut file, ";\n").unwrap();
+    writeln!(&mut file, ";").unwrap();
 
-    write!(&mut file, "#[allow(clippy::unreadable_literal)]\n").unwrap();
+    writeln!(&mut file, "#[allow(clippy::unreadable_literal)]").unwrap();
     write!(&mut file, "static FINAL_KEYS: phf::Set<&'static str> = ").unwrap();
     phf_codegen::Set::new()
+        .entry("dependentRequired")
         .entry("enum")
         .entry("required")
+        .entry("$vocabulary")
         .entry("type")
         .build(&mut file)
         .unwrap();
-    write!(&mut file, ";\n").unwrap();
+    writeln!(&mut file, ";").unwrap();
 
-    write!(&mut file, "#[allow(clippy::unreadable_literal)]\n").unwrap();
+    writeln!(&mut file, "#[allow(clippy::unreadable_literal)]").unwrap();
     write!(
         &mut file,
         "const ALLOW_NON_CONSUMED_KEYS: phf::Set<&'static str> = "
@@ -69,8 +72,14 @@ fn main() {
     .unwrap();
     phf_codegen::Set::new()
         .entry("definitions")
+        .entry("$defs")
         .entry("$schema")
         .entry("$id")
+        .entry("$anchor")
+        .entry("$dynamicAnchor")
+        .entry("$recursiveAnchor")
+        .entry("$vocabulary")
+        .entry("$comment")
         .entry("default")
         .entry("title")
         .entry("description")
@@ -78,5 +87,5 @@ fn main() {
         .entry("examples")
         .build(&mut file)
         .unwrap();
-    write!(&mut file, ";\n").unwrap();
+    writeln!(&mut file, ";").unwrap();
 }
@@ -49,7 +49,7 @@ macro_rules! impl_basic_err {
 
         impl ::std::fmt::Display for $err {
             fn fmt(&self, formatter: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
-                std::fmt::Display::fmt(&self.to_string(), formatter)
+                formatter.write_str($code)
             }
         }
     };
@@ -137,6 +137,70 @@ pub struct Pattern {
 }
 impl_err!(Pattern, "pattern", "Pattern condition is not met");
 
+#[derive(Debug)]
+pub struct ReadOnly {
+    pub path: String,
+}
+impl_err!(
+    ReadOnly,
+    "read_only",
+    "The value is read-only and can't be sent in a request"
+);
+
+#[derive(Debug)]
+pub struct WriteOnly {
+    pub path: String,
+}
+impl_err!(
+    WriteOnly,
+    "write_only",
+    "The value is write-only and can't be sent in a response"
+);
+
+#[derive(Debug)]
+pub struct Deprecated {
+    pub path: String,
+    /// Location of the schema marked `deprecated`.
+    pub schema: url::Url,
+}
+impl_err!(Deprecated, "deprecated", "The value is deprecated");
+
+#[derive(Debug)]
+pub struct MaxDepth {
+    pub path: String,
+    pub detail: String,
+}
+impl_err!(
+    MaxDepth,
+    "max_depth",
+    "The schemas applied to the value nest too deep",
+    +detail
+);
+
+#[derive(Debug)]
+pub struct PatternInputTooLong {
+    pub path: String,
+    pub detail: String,
+}
+impl_err!(
+    PatternInputTooLong,
+    "pattern_input_too_long",
+    "The string is too long to be matched against a pattern",
+    +detail
+);
+
+#[derive(Debug)]
+pub struct PatternTooComplex {
+    pub path: String,
+    pub detail: String,
+}
+impl_err!(
+    PatternTooComplex,
+    "pattern_too_complex",
+    "The string takes too long to be matched against a pattern",
+    +detail
+);
+
 #[derive(Debug)]
 #[allow(missing_copy_implementations)]
 pub struct WrongType {
@@ -278,3 +342,11 @@ pub struct Const {
     pub path: String,
 }
 impl_err!(Const, "const", "Const condition is not met");
+
+#[derive(Debug)]
+#[allow(missing_copy_implementations)]
+pub struct Content {
+    pub path: String,
+    pub detail: String,
+}
+impl_err!(Content, "content", "Content condition is not met", +detail);
@@ -1,4 +1,6 @@
+use serde_json;
 use simd_json::json;
+use std::convert::TryFrom;
 use url::Url;
 use uuid::Uuid;
 use value_trait::*;
@@ -7,16 +9,32 @@ use super::schema;
 
 pub fn encode(string: &str) -> String {
     percent_encoding::percent_encode(
-        string
-            .replace("~", "~0")
-            .replace("/", "~1")
-            .replace("%", "%25")
-            .as_bytes(),
+        string.replace("~", "~0").replace("/", "~1").as_bytes(),
         percent_encoding::NON_ALPHANUMERIC,
     )
     .to_string()
 }
 
+pub fn decode(string: &str) -> String {
+    percent_encoding::percent_decode_str(string)
+        .decode_utf8_lossy()
+        .replace("~1", "/")
+        .replace("~0", "~")
+}
+
+/// Escapes a reference token of a JSON pointer.
+pub fn escape_pointer_token(token: &str) -> String {
+    token.replace("~", "~0").replace("/", "~1")
+}
+
+/// Joins reference tokens into a JSON pointer.
+pub fn pointer(tokens: &[String]) -> String {
+    tokens
+        .iter()
+        .map(|token| format!("/{}", escape_pointer_token(token)))
+        .collect()
+}
+
 pub fn connect(strings: &[&str]) -> String {
     strings
         .iter()
@@ -30,7 +48,7 @@ pub fn generate_id() -> Url {
     Url::parse(&format!("json-schema://{}", uuid)).unwrap()
 }
 
-pub fn parse_url_key<V: Value>(key: &str, obj: &V) -> Result<Option<Url>, schema::SchemaError>
+pub fn parse_url_key<V>(key: &str, obj: &V) -> Result<Option<Url>, schema::SchemaError>
 where
     V: Value,
     <V as Value>::Key: std::borrow::Borrow<str> + std::hash::Hash + Eq + std::convert::AsRef<str>,
@@ -46,21 +64,37 @@ where
     }
 }
 
+/// Splits a parsed `$id` into the base URL of a new schema resource and a plain-name fragment,
+/// which only names the schema, as `$anchor` does since draft 2019-09.
+pub fn split_id(mut id: Url) -> (Option<Url>, Option<Url>) {
+    match id.fragment() {
+        Some(fragment) if !fragment.is_empty() => (None, Some(id)),
+        _ => {
+            id.set_fragment(None);
+            (Some(id), None)
+        }
+    }
+}
+
+pub fn anchor_url(base: &Url, anchor: &str) -> Url {
+    let mut url = base.clone();
+    url.set_fragment(Some(anchor));
+    url
+}
+
 pub fn alter_fragment_path(mut url: Url, new_fragment: String) -> Url {
-    let normalized_fragment = if new_fragment.starts_with('/') {
-        &new_fragment[1..]
-    } else {
-        new_fragment.as_ref()
-    };
+    let normalized_fragment = new_fragment
+        .strip_prefix('/')
+        .unwrap_or_else(|| new_fragment.as_ref());
 
     let result_fragment = match url.fragment() {
-        Some(ref fragment) if !fragment.is_empty() => {
+        Some(fragment) if !fragment.is_empty() => {
             if !fragment.starts_with('/') {
                 let mut result_fragment = "".to_string();
                 let mut fragment_parts = fragment.split('/').map(|s| s.to_string());
-                result_fragment.push_str("#");
+                result_fragment.push('#');
                 result_fragment.push_str(fragment_parts.next().unwrap().as_ref());
-                result_fragment.push_str("/");
+                result_fragment.push('/');
                 result_fragment.push_str(normalized_fragment.as_ref());
                 result_fragment
             } else {
@@ -86,7 +120,7 @@ pub fn serialize_schema_path(url: &Url) -> (String, Option<String>) {
                     .split('/')
                     .map(|s| s.to_string())
                     .collect::<Vec<String>>();
-                url_str.push_str("#");
+                url_str.push('#');
                 url_str.push_str(fragment_parts[0].as_ref());
                 let fragment = if fragment_parts.len() > 1 {
                     Some("/".to_string() + fragment_parts[1..].join("/").as_ref())
@@ -102,7 +136,7 @@ pub fn serialize_schema_path(url: &Url) -> (String, Option<String>) {
     }
 }
 
-pub fn convert_boolean_schema<V: Value>(val: V) -> V
+pub fn convert_boolean_schema<V>(val: V) -> V
 where
     V: Value + std::convert::From<simd_json::value::owned::Value>,
     <V as Value>::Key: std::borrow::Borrow<str> + std::hash::Hash + Eq + std::convert::AsRef<str>,
@@ -139,3 +173,13 @@ where
         None => Ok(None),
     }
 }
+
+/// Parses a JSON document. The parsers of simd-json 0.3 index their buffers with unchecked
+/// accesses that trip the precondition checks of debug builds on any input, so this goes through
+/// serde_json and converts the result. It can parse with simd-json again once the dependency is
+/// upgraded past that.
+pub fn parse_json(bytes: &[u8]) -> Option<simd_json::value::owned::Value> {
+    serde_json::from_slice::<serde_json::Value>(bytes)
+        .ok()
+        .and_then(|document| simd_json::value::owned::Value::try_from(document).ok())
+}
@@ -34,10 +34,10 @@ where
 
         if contains.is_object() || contains.is_bool() {
             Ok(Some(Box::new(validators::Contains {
-                url: helpers::alter_fragment_path(
+                url: ctx.link(helpers::alter_fragment_path(
                     ctx.url.clone(),
                     [ctx.escaped_fragment().as_ref(), "contains"].join("/"),
-                ),
+                )),
             })))
         } else {
             Err(schema::SchemaError::Malformed {
@@ -0,0 +1,244 @@
+use simd_json::value::owned::Value as OwnedValue;
+use value_trait::*;
+
+use super::helpers;
+use super::schema;
+use super::validators;
+
+/// Validates strings embedding a document, through `contentEncoding`, `contentMediaType` and
+/// `contentSchema`. Not part of any draft's keywords, register it with `Scope::add_keyword` in
+/// the dialects and the content vocabularies that should run it.
+#[allow(missing_copy_implementations)]
+pub struct Content;
+impl<V> super::Keyword<V> for Content
+where
+    V: Value
+        + std::clone::Clone
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::fmt::Display
+        + std::marker::Sync
+        + std::marker::Send
+        + std::cmp::PartialEq,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::hash::Hash
+        + Eq
+        + std::convert::AsRef<str>
+        + std::fmt::Debug
+        + std::string::ToString
+        + std::marker::Sync
+        + std::marker::Send,
+{
+    fn compile(
+        &self,
+        def: &OwnedValue,
+        ctx: &schema::WalkContext<'_>,
+    ) -> super::KeywordCompilationResult<V> {
+        let encoding = def.get("contentEncoding");
+        let media_type = def.get("contentMediaType");
+        let content_schema = def.get("contentSchema");
+
+        if encoding.is_none() && media_type.is_none() {
+            return Ok(None);
+        }
+
+        let encoding = match encoding {
+            Some(encoding) => {
+                match encoding
+                    .as_str()
+                    .and_then(validators::content::Encoding::from_name)
+                {
+                    Some(encoding) => Some(encoding),
+                    None => {
+                        return Err(schema::SchemaError::Malformed {
+                            path: ctx.fragment.join("/"),
+                            detail: "The value of contentEncoding must be one of base64, \
+                                     base64url or quoted-printable"
+                                .to_string(),
+                        })
+                    }
+                }
+            }
+            None => None,
+        };
+
+        let json = match media_type {
+            Some(media_type) => match media_type.as_str() {
+                // Parameters such as `charset` don't change how the document is parsed
+                Some(media_type) => media_type
+                    .split(';')
+                    .next()
+                    .unwrap()
+                    .trim()
+                    .eq_ignore_ascii_case("application/json"),
+                None => {
+                    return Err(schema::SchemaError::Malformed {
+                        path: ctx.fragment.join("/"),
+                        detail: "The value of contentMediaType must be a string".to_string(),
+                    })
+                }
+            },
+            None => false,
+        };
+
+        // `contentSchema` only applies to documents of a known media type
+        let schema = match content_schema {
+            Some(content_schema) if content_schema.is_object() || content_schema.is_bool() => {
+                if json {
+                    Some(ctx.link(helpers::alter_fragment_path(
+                        ctx.url.clone(),
+                        [ctx.escaped_fragment().as_ref(), "contentSchema"].join("/"),
+                    )))
+                } else {
+                    None
+                }
+            }
+            Some(_) => {
+                return Err(schema::SchemaError::Malformed {
+                    path: ctx.fragment.join("/"),
+                    detail: "The value of contentSchema must be an object or a boolean".to_string(),
+                })
+            }
+            None => None,
+        };
+
+        Ok(Some(Box::new(validators::Content {
+            encoding,
+            json,
+            schema,
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::error;
+    use super::super::super::schema;
+    use super::super::super::scope;
+    use super::Content;
+    use simd_json::json;
+    use simd_json::value::owned::Value as OwnedValue;
+
+    fn scope() -> scope::Scope<OwnedValue> {
+        let mut scope = scope::Scope::new();
+        for uri in [
+            schema::Draft::Draft7.metaschema(),
+            "https://json-schema.org/draft/2020-12/vocab/content",
+        ]
+        .iter()
+        {
+            scope.add_keyword(
+                &url::Url::parse(uri).unwrap(),
+                vec![
+                    "contentEncoding".to_string(),
+                    "contentMediaType".to_string(),
+                    "contentSchema".to_string(),
+                ],
+                Content,
+            );
+        }
+        scope
+    }
+
+    #[test]
+    fn base64_json_document() {
+        let mut scope = scope();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "contentEncoding": "base64",
+                    "contentMediaType": "application/json",
+                    "contentSchema": { "required": ["a"] }
+                }),
+                true,
+            )
+            .unwrap();
+
+        // {"a":1}
+        assert!(schema.validate(&json!("eyJhIjoxfQ==")).is_valid());
+        // {"b":1}
+        let state = schema.validate(&json!("eyJiIjoxfQ=="));
+        assert!(!state.is_valid());
+        assert!(state.errors[0].is::<error::Content>());
+        // {"a":1
+        assert!(!schema.validate(&json!("eyJhIjox")).is_valid());
+        assert!(!schema.validate(&json!("not base64!")).is_valid());
+        assert!(schema.validate(&json!(1)).is_valid());
+    }
+
+    #[test]
+    fn json_without_encoding() {
+        let mut scope = scope();
+        let schema = scope
+            .compile_and_return(
+                json!({ "contentMediaType": "application/json; charset=utf-8" }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!("{\"a\": [1, 2]}")).is_valid());
+        assert!(!schema.validate(&json!("{\"a\": ")).is_valid());
+    }
+
+    #[test]
+    fn other_media_types_are_not_parsed() {
+        let mut scope = scope();
+        let schema = scope
+            .compile_and_return(
+                json!({ "contentEncoding": "quoted-printable", "contentMediaType": "text/plain" }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!("caf=C3=A9")).is_valid());
+        assert!(!schema.validate(&json!("caf=C3=A")).is_valid());
+    }
+
+    #[test]
+    fn only_in_registered_dialects() {
+        let mut scope = scope();
+        scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "$id": "https://example.com/meta/content",
+                    "$vocabulary": {
+                        "https://json-schema.org/draft/2020-12/vocab/core": true,
+                        "https://json-schema.org/draft/2020-12/vocab/content": true
+                    }
+                }),
+                false,
+            )
+            .unwrap();
+
+        let is_checked = |scope: &mut scope::Scope<OwnedValue>, metaschema: &str| {
+            let schema = scope
+                .compile_and_return(
+                    json!({ "$schema": metaschema, "contentMediaType": "application/json" }),
+                    false,
+                )
+                .unwrap();
+            !schema.validate(&json!("{")).is_valid()
+        };
+        assert!(is_checked(
+            &mut scope,
+            "http://json-schema.org/draft-07/schema#"
+        ));
+        assert!(is_checked(&mut scope, "https://example.com/meta/content"));
+        assert!(!is_checked(
+            &mut scope,
+            "http://json-schema.org/draft-06/schema#"
+        ));
+        assert!(!is_checked(
+            &mut scope,
+            "https://json-schema.org/draft/2020-12/schema"
+        ));
+    }
+
+    #[test]
+    fn unknown_encoding() {
+        let mut scope = scope();
+        assert!(scope
+            .compile_and_return(json!({ "contentEncoding": "base32" }), true)
+            .is_err());
+    }
+}
@@ -46,15 +46,17 @@ where
             if item.is_object() || item.is_bool() {
                 items.insert(
                     key.clone().to_string(),
-                    validators::dependencies::DepKind::Schema(helpers::alter_fragment_path(
-                        ctx.url.clone(),
-                        [
-                            ctx.escaped_fragment().as_ref(),
-                            "dependencies",
-                            helpers::encode(key.as_ref()).as_ref(),
-                        ]
-                        .join("/"),
-                    )),
+                    validators::dependencies::DepKind::Schema(
+                        ctx.link(helpers::alter_fragment_path(
+                            ctx.url.clone(),
+                            [
+                                ctx.escaped_fragment().as_ref(),
+                                "dependencies",
+                                helpers::encode(key.as_ref()).as_ref(),
+                            ]
+                            .join("/"),
+                        )),
+                    ),
                 );
             } else if item.is_array() {
                 let item = item.as_array().unwrap();
@@ -86,3 +88,229 @@ where
         Ok(Some(Box::new(validators::Dependencies { items })))
     }
 }
+
+#[allow(missing_copy_implementations)]
+pub struct DependentRequired;
+impl<V> super::Keyword<V> for DependentRequired
+where
+    V: Value
+        + std::clone::Clone
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::fmt::Display
+        + std::marker::Sync
+        + std::marker::Send
+        + std::cmp::PartialEq,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::hash::Hash
+        + Eq
+        + std::convert::AsRef<str>
+        + std::fmt::Debug
+        + std::string::ToString
+        + std::marker::Sync
+        + std::marker::Send,
+{
+    fn compile(
+        &self,
+        def: &OwnedValue,
+        ctx: &schema::WalkContext<'_>,
+    ) -> super::KeywordCompilationResult<V> {
+        let deps = keyword_key_exists!(def, "dependentRequired");
+
+        if !deps.is_object() {
+            return Err(schema::SchemaError::Malformed {
+                path: ctx.fragment.join("/"),
+                detail: "The value of this keyword must be an object.".to_string(),
+            });
+        }
+
+        let deps = deps.as_object().unwrap();
+        let mut items = hashbrown::HashMap::new();
+
+        for (key, item) in deps.iter() {
+            if item.is_array() {
+                let item = item.as_array().unwrap();
+                let mut keys = vec![];
+                for key in item.iter() {
+                    if key.is_str() {
+                        keys.push(key.as_str().unwrap().to_string())
+                    } else {
+                        return Err(schema::SchemaError::Malformed {
+                            path: ctx.fragment.join("/"),
+                            detail: "Each element must be a string, and elements in the array must be unique.".to_string()
+                        });
+                    }
+                }
+                items.insert(
+                    key.clone().to_string(),
+                    validators::dependencies::DepKind::Property(keys),
+                );
+            } else {
+                return Err(schema::SchemaError::Malformed {
+                    path: ctx.fragment.join("/"),
+                    detail: "Each value of this object must be an array.".to_string(),
+                });
+            }
+        }
+
+        Ok(Some(Box::new(validators::Dependencies { items })))
+    }
+}
+
+#[allow(missing_copy_implementations)]
+pub struct DependentSchemas;
+impl<V> super::Keyword<V> for DependentSchemas
+where
+    V: Value
+        + std::clone::Clone
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::fmt::Display
+        + std::marker::Sync
+        + std::marker::Send
+        + std::cmp::PartialEq,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::hash::Hash
+        + Eq
+        + std::convert::AsRef<str>
+        + std::fmt::Debug
+        + std::string::ToString
+        + std::marker::Sync
+        + std::marker::Send,
+{
+    fn compile(
+        &self,
+        def: &OwnedValue,
+        ctx: &schema::WalkContext<'_>,
+    ) -> super::KeywordCompilationResult<V> {
+        let deps = keyword_key_exists!(def, "dependentSchemas");
+
+        if !deps.is_object() {
+            return Err(schema::SchemaError::Malformed {
+                path: ctx.fragment.join("/"),
+                detail: "The value of this keyword must be an object.".to_string(),
+            });
+        }
+
+        let deps = deps.as_object().unwrap();
+        let mut items = hashbrown::HashMap::new();
+
+        for (key, item) in deps.iter() {
+            if item.is_object() || item.is_bool() {
+                items.insert(
+                    key.clone().to_string(),
+                    validators::dependencies::DepKind::Schema(
+                        ctx.link(helpers::alter_fragment_path(
+                            ctx.url.clone(),
+                            [
+                                ctx.escaped_fragment().as_ref(),
+                                "dependentSchemas",
+                                helpers::encode(key.as_ref()).as_ref(),
+                            ]
+                            .join("/"),
+                        )),
+                    ),
+                );
+            } else {
+                return Err(schema::SchemaError::Malformed {
+                    path: ctx.fragment.join("/"),
+                    detail: "Each value of this object must be an object or a boolean.".to_string(),
+                });
+            }
+        }
+
+        Ok(Some(Box::new(validators::Dependencies { items })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::scope;
+    use simd_json::json;
+    use simd_json::value::owned::Value as OwnedValue;
+
+    #[test]
+    fn dependent_required() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "dependentRequired": { "credit_card": ["billing_address"] }
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!({ "name": "x" })).is_valid());
+        assert!(schema
+            .validate(&json!({ "credit_card": 1, "billing_address": "x" }))
+            .is_valid());
+
+        let state = schema.validate(&json!({ "credit_card": 1 }));
+        assert_eq!(state.errors.len(), 1);
+        assert_eq!(state.errors[0].get_path(), "/billing_address");
+    }
+
+    #[test]
+    fn dependent_schemas() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "dependentSchemas": {
+                        "credit_card": { "required": ["billing_address"] }
+                    }
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!({ "name": "x" })).is_valid());
+        assert!(!schema.validate(&json!({ "credit_card": 1 })).is_valid());
+    }
+
+    #[test]
+    fn dependencies_only_apply_in_draft7() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "dependencies": { "credit_card": ["billing_address"] }
+                }),
+                false,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!({ "credit_card": 1 })).is_valid());
+    }
+
+    #[test]
+    fn refs_into_defs() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "$defs": {
+                        "address": { "required": ["street"] }
+                    },
+                    "dependentSchemas": {
+                        "billing_address": {
+                            "properties": { "billing_address": { "$ref": "#/$defs/address" } }
+                        }
+                    }
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema
+            .validate(&json!({ "billing_address": { "street": "x" } }))
+            .is_valid());
+
+        let state = schema.validate(&json!({ "billing_address": {} }));
+        assert!(state.missing.is_empty());
+        assert!(!state.is_valid());
+    }
+}
@@ -0,0 +1,142 @@
+use simd_json::value::owned::Value as OwnedValue;
+use value_trait::*;
+
+use super::super::helpers;
+use super::schema;
+use super::validators;
+
+/// `deprecated`, which reports a warning for the values its schema applies to.
+#[allow(missing_copy_implementations)]
+pub struct Deprecated;
+impl<V> super::Keyword<V> for Deprecated
+where
+    V: Value
+        + std::clone::Clone
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::fmt::Display
+        + std::marker::Sync
+        + std::marker::Send
+        + std::cmp::PartialEq
+        + 'static,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::hash::Hash
+        + Eq
+        + std::convert::AsRef<str>
+        + std::fmt::Debug
+        + std::string::ToString
+        + std::marker::Sync
+        + std::marker::Send,
+{
+    fn compile(
+        &self,
+        def: &OwnedValue,
+        ctx: &schema::WalkContext<'_>,
+    ) -> super::KeywordCompilationResult<V> {
+        let deprecated = keyword_key_exists!(def, "deprecated");
+
+        if !deprecated.is_bool() {
+            return Err(schema::SchemaError::Malformed {
+                path: ctx.fragment.join("/"),
+                detail: "The value of `deprecated` must be a boolean".to_string(),
+            });
+        }
+
+        if !deprecated.as_bool().unwrap() {
+            return Ok(None);
+        }
+
+        Ok(Some(Box::new(validators::Deprecated {
+            schema: helpers::alter_fragment_path(ctx.url.clone(), ctx.escaped_fragment()),
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::error;
+    use super::super::super::scope;
+    use simd_json::json;
+    use simd_json::value::owned::Value as OwnedValue;
+
+    #[test]
+    fn warns_for_deprecated_values() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2019-09/schema",
+                    "$id": "http://example.com/schema",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "nickname": { "type": "string", "deprecated": true },
+                        "legacy": { "deprecated": false }
+                    }
+                }),
+                true,
+            )
+            .unwrap();
+
+        let state = schema.validate(&json!({ "name": "a", "nickname": "b", "legacy": 1 }));
+        assert!(state.is_valid());
+        assert_eq!(state.warnings.len(), 1);
+
+        let warning = state.warnings[0].downcast::<error::Deprecated>().unwrap();
+        assert_eq!(warning.path, "/nickname");
+        assert_eq!(
+            warning.schema.as_str(),
+            "http://example.com/schema#/properties/nickname"
+        );
+
+        assert!(schema.validate(&json!({ "name": "a" })).warnings.is_empty());
+    }
+
+    #[test]
+    fn warns_under_combinators() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2019-09/schema",
+                    "properties": {
+                        "one": {
+                            "oneOf": [{ "type": "string", "deprecated": true }, { "type": "integer" }]
+                        },
+                        "any": {
+                            "anyOf": [{ "type": "string", "deprecated": true }, { "type": "integer" }]
+                        }
+                    }
+                }),
+                true,
+            )
+            .unwrap();
+
+        let state = schema.validate(&json!({ "one": "a" }));
+        assert_eq!(state.warnings.len(), 1);
+        assert_eq!(
+            state.warnings[0]
+                .downcast::<error::Deprecated>()
+                .unwrap()
+                .path,
+            "/one"
+        );
+        assert_eq!(schema.validate(&json!({ "any": "a" })).warnings.len(), 1);
+        assert!(schema
+            .validate(&json!({ "one": 1, "any": 1 }))
+            .warnings
+            .is_empty());
+    }
+
+    #[test]
+    fn malformed() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let result = scope.compile_and_return(
+            json!({
+                "$schema": "https://json-schema.org/draft/2019-09/schema",
+                "deprecated": "yes"
+            }),
+            true,
+        );
+
+        assert!(result.is_err());
+    }
+}
@@ -1,18 +1,23 @@
 use hashbrown::HashMap;
-use value_trait::*;
 use simd_json::value::owned::Value as OwnedValue;
+use value_trait::*;
 
 use super::schema;
 use super::validators;
+use super::validators::formats;
 
 pub type FormatBuilders<V> = HashMap<String, Box<dyn super::Keyword<V> + Send + Sync>>;
 
+// Makes the validator of a format, which takes no settings
+type FormatValidator<V> = fn() -> validators::BoxedValidator<V>;
+
 fn default_formats<V>() -> FormatBuilders<V>
 where
     V: Value
         + std::clone::Clone
         + std::convert::From<simd_json::value::owned::Value>
-        + std::fmt::Display,
+        + std::fmt::Display
+        + 'static,
     <V as Value>::Key: std::borrow::Borrow<str>
         + std::hash::Hash
         + Eq
@@ -22,69 +27,45 @@ where
         + std::marker::Sync
         + std::marker::Send,
 {
-    let mut map: FormatBuilders<V> = HashMap::new();
+    let format_validators: [(&str, FormatValidator<V>); 19] = [
+        ("date-time", || Box::new(formats::DateTime)),
+        ("email", || Box::new(formats::Email)),
+        ("hostname", || Box::new(formats::Hostname)),
+        ("ipv4", || Box::new(formats::Ipv4)),
+        ("ipv6", || Box::new(formats::Ipv6)),
+        ("uri", || Box::new(formats::Uri)),
+        ("uri-reference", || Box::new(formats::UriReference)),
+        ("uuid", || Box::new(formats::Uuid)),
+        ("date", || Box::new(formats::Date)),
+        ("time", || Box::new(formats::Time)),
+        ("duration", || Box::new(formats::Duration)),
+        ("idn-email", || Box::new(formats::IdnEmail)),
+        ("idn-hostname", || Box::new(formats::IdnHostname)),
+        ("iri", || Box::new(formats::Iri)),
+        ("iri-reference", || Box::new(formats::IriReference)),
+        ("uri-template", || Box::new(formats::UriTemplate)),
+        ("json-pointer", || Box::new(formats::JsonPointer)),
+        ("relative-json-pointer", || {
+            Box::new(formats::RelativeJsonPointer)
+        }),
+        ("regex", || Box::new(formats::Regex)),
+    ];
 
-    let date_time_builder = Box::new(|_def: &OwnedValue, _ctx: &schema::WalkContext<'_>| {
-        Ok(Some(
-            Box::new(validators::formats::DateTime) as validators::BoxedValidator<V>
-        ))
-    });
-    map.insert("date-time".to_string(), date_time_builder);
-
-    let email_builder = Box::new(|_def: &OwnedValue, _ctx: &schema::WalkContext<'_>| {
-        Ok(Some(
-            Box::new(validators::formats::Email) as validators::BoxedValidator<V>
-        ))
-    });
-    map.insert("email".to_string(), email_builder);
-
-    let hostname_builder = Box::new(|_def: &OwnedValue, _ctx: &schema::WalkContext<'_>| {
-        Ok(Some(
-            Box::new(validators::formats::Hostname) as validators::BoxedValidator<V>
-        ))
-    });
-    map.insert("hostname".to_string(), hostname_builder);
-
-    let ipv4_builder = Box::new(|_def: &OwnedValue, _ctx: &schema::WalkContext<'_>| {
-        Ok(Some(
-            Box::new(validators::formats::Ipv4) as validators::BoxedValidator<V>
-        ))
-    });
-    map.insert("ipv4".to_string(), ipv4_builder);
-
-    let ipv6_builder = Box::new(|_def: &OwnedValue, _ctx: &schema::WalkContext<'_>| {
-        Ok(Some(
-            Box::new(validators::formats::Ipv6) as validators::BoxedValidator<V>
-        ))
-    });
-    map.insert("ipv6".to_string(), ipv6_builder);
-
-    let uri_builder = Box::new(|_def: &OwnedValue, _ctx: &schema::WalkContext<'_>| {
-        Ok(Some(
-            Box::new(validators::formats::Uri) as validators::BoxedValidator<V>
-        ))
-    });
-    map.insert("uri".to_string(), uri_builder);
-
-    let uri_reference_builder = Box::new(|_def: &OwnedValue, _ctx: &schema::WalkContext<'_>| {
-        Ok(Some(
-            Box::new(validators::formats::UriReference) as validators::BoxedValidator<V>
-        ))
-    });
-    map.insert("uri-reference".to_string(), uri_reference_builder);
-
-    let uuid_builder = Box::new(|_def: &OwnedValue, _ctx: &schema::WalkContext<'_>| {
-        Ok(Some(
-            Box::new(validators::formats::Uuid) as validators::BoxedValidator<V>
-        ))
-    });
-    map.insert("uuid".to_string(), uuid_builder);
+    let mut map: FormatBuilders<V> = HashMap::new();
+    for (name, validator) in format_validators.iter().cloned() {
+        let builder = Box::new(move |_def: &OwnedValue, _ctx: &schema::WalkContext<'_>| {
+            Ok(Some(validator()))
+        });
+        map.insert(name.to_string(), builder);
+    }
 
     map
 }
 
 pub struct Format<V> {
     pub formats: FormatBuilders<V>,
+    // Whether failures are errors whatever the format mode
+    asserts: bool,
 }
 
 impl<V> Format<V>
@@ -92,7 +73,8 @@ where
     V: Value
         + std::clone::Clone
         + std::convert::From<simd_json::value::owned::Value>
-        + std::fmt::Display,
+        + std::fmt::Display
+        + 'static,
     <V as Value>::Key: std::borrow::Borrow<str>
         + std::hash::Hash
         + Eq
@@ -102,40 +84,57 @@ where
         + std::marker::Sync
         + std::marker::Send,
 {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Format<V> {
         Format {
             formats: default_formats(),
+            asserts: false,
         }
     }
 
+    /// Makes failures errors whatever the format mode, as the format-assertion vocabulary does.
+    pub fn asserting(mut self) -> Format<V> {
+        self.asserts = true;
+        self
+    }
+
     pub fn with<F>(build_formats: F) -> Format<V>
     where
         F: FnOnce(&mut FormatBuilders<V>),
     {
         let mut formats = default_formats();
         build_formats(&mut formats);
-        Format { formats }
+        Format {
+            formats,
+            asserts: false,
+        }
     }
 }
 
 impl<V> super::Keyword<V> for Format<V>
 where
-    V: Value
-        + std::clone::Clone
-        + std::convert::From<simd_json::value::owned::Value>
-        + std::fmt::Display,
+    V: Value + 'static,
+    <V as Value>::Key: std::borrow::Borrow<str> + std::hash::Hash + Eq + std::convert::AsRef<str>,
 {
-    fn compile(&self, def: &OwnedValue, ctx: &schema::WalkContext<'_>) -> super::KeywordCompilationResult<V>
-    where
-        <V as Value>::Key: std::borrow::Borrow<str> + std::hash::Hash + Eq,
-    {
+    fn compile(
+        &self,
+        def: &OwnedValue,
+        ctx: &schema::WalkContext<'_>,
+    ) -> super::KeywordCompilationResult<V> {
         let format = keyword_key_exists!(def, "format");
 
         if format.as_str().is_some() {
             let format = format.as_str().unwrap();
-            match self.formats.get(format) {
-                Some(keyword) => keyword.compile(def, ctx),
-                None => Ok(None),
+            let validator = match self.formats.get(format) {
+                Some(keyword) => keyword.compile(def, ctx)?,
+                None => None,
+            };
+
+            match validator {
+                Some(validator) if !self.asserts && !ctx.format_mode.asserts(format) => {
+                    Ok(Some(Box::new(validators::formats::Annotated { validator })))
+                }
+                validator => Ok(validator),
             }
         } else {
             Err(schema::SchemaError::Malformed {
@@ -145,3 +144,341 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::error;
+    use super::super::super::schema;
+    use super::super::super::scope;
+    use simd_json::json;
+    use simd_json::value::owned::Value as OwnedValue;
+
+    fn is_valid(format: &str, value: OwnedValue) -> bool {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(json!({ "format": format }), false)
+            .unwrap();
+        schema.validate(&value).is_valid()
+    }
+
+    #[test]
+    fn date_time() {
+        assert!(is_valid("date-time", json!("2020-03-20T09:15:00Z")));
+        assert!(is_valid(
+            "date-time",
+            json!("2020-03-20T09:15:00.123+01:00")
+        ));
+        assert!(!is_valid("date-time", json!("2020-03-20")));
+        assert!(!is_valid("date-time", json!("20.03.2020 09:15")));
+    }
+
+    #[test]
+    fn email() {
+        assert!(is_valid("email", json!("ernad@example.com")));
+        assert!(!is_valid("email", json!("ernad.example.com")));
+    }
+
+    #[test]
+    fn hostname() {
+        assert!(is_valid("hostname", json!("example.com")));
+        assert!(!is_valid("hostname", json!("-example-.com")));
+    }
+
+    #[test]
+    fn ipv4() {
+        assert!(is_valid("ipv4", json!("127.0.0.1")));
+        assert!(!is_valid("ipv4", json!("127.0.0.256")));
+        assert!(!is_valid("ipv4", json!("::1")));
+    }
+
+    #[test]
+    fn ipv6() {
+        assert!(is_valid("ipv6", json!("::1")));
+        assert!(is_valid("ipv6", json!("2001:db8::ff00:42:8329")));
+        assert!(!is_valid("ipv6", json!("127.0.0.1")));
+    }
+
+    #[test]
+    fn uri() {
+        assert!(is_valid(
+            "uri",
+            json!("https://example.com/path?query#fragment")
+        ));
+        assert!(!is_valid("uri", json!("/relative/path")));
+    }
+
+    #[test]
+    fn uri_reference() {
+        assert!(is_valid("uri-reference", json!("/relative/path")));
+        assert!(is_valid("uri-reference", json!("https://example.com")));
+        assert!(!is_valid("uri-reference", json!("http://[::1")));
+    }
+
+    #[test]
+    fn uuid() {
+        assert!(is_valid(
+            "uuid",
+            json!("936da01f-9abd-4d9d-80c7-02af85c822a8")
+        ));
+        assert!(!is_valid("uuid", json!("936da01f-9abd-4d9d-80c7")));
+    }
+
+    #[test]
+    fn hostname_and_email_are_ascii() {
+        assert!(!is_valid("hostname", json!("bücher.example")));
+        assert!(!is_valid("email", json!("ernad@bücher.example")));
+    }
+
+    #[test]
+    fn date() {
+        assert!(is_valid("date", json!("2020-02-29")));
+        assert!(!is_valid("date", json!("2019-02-29")));
+        assert!(!is_valid("date", json!("2020-13-01")));
+        assert!(!is_valid("date", json!("2020-1-01")));
+        assert!(!is_valid("date", json!("2020-03-20T09:15:00Z")));
+    }
+
+    #[test]
+    fn time() {
+        assert!(is_valid("time", json!("09:15:00Z")));
+        assert!(is_valid("time", json!("09:15:00.123+01:00")));
+        assert!(is_valid("time", json!("23:59:60Z")));
+        assert!(is_valid("time", json!("15:59:60-08:00")));
+        assert!(!is_valid("time", json!("22:59:60Z")));
+        assert!(!is_valid("time", json!("24:00:00Z")));
+        assert!(!is_valid("time", json!("09:15:00")));
+        assert!(!is_valid("time", json!("09:15:00+24:00")));
+        assert!(!is_valid("time", json!("09:15:00.Z")));
+    }
+
+    #[test]
+    fn duration() {
+        assert!(is_valid("duration", json!("P4DT12H30M5S")));
+        assert!(is_valid("duration", json!("P1Y2M")));
+        assert!(is_valid("duration", json!("PT36H")));
+        assert!(is_valid("duration", json!("P2W")));
+        assert!(!is_valid("duration", json!("P")));
+        assert!(!is_valid("duration", json!("PT")));
+        assert!(!is_valid("duration", json!("P1D2H")));
+        assert!(!is_valid("duration", json!("P2M1Y")));
+        assert!(!is_valid("duration", json!("P1Y1D")));
+        assert!(!is_valid("duration", json!("P1WT1H")));
+        assert!(!is_valid("duration", json!("4DT12H")));
+    }
+
+    #[test]
+    fn idn_email() {
+        assert!(is_valid("idn-email", json!("실례@실례.테스트")));
+        assert!(is_valid("idn-email", json!("ernad@example.com")));
+        assert!(!is_valid("idn-email", json!("2962")));
+    }
+
+    #[test]
+    fn idn_hostname() {
+        assert!(is_valid("idn-hostname", json!("실례.테스트")));
+        assert!(is_valid("idn-hostname", json!("bücher.example")));
+        assert!(!is_valid("idn-hostname", json!("-bücher.example")));
+    }
+
+    #[test]
+    fn iri() {
+        assert!(is_valid("iri", json!("http://ƒøø.ßår/?∂éœ=πîx#πîüx")));
+        assert!(!is_valid("iri", json!("/abc")));
+        assert!(!is_valid("iri", json!("http://example.com/a b")));
+    }
+
+    #[test]
+    fn iri_reference() {
+        assert!(is_valid("iri-reference", json!("/âππ")));
+        assert!(is_valid("iri-reference", json!("#ƒrägmênt")));
+        assert!(!is_valid("iri-reference", json!("\\\\WINDOWS\\filëßåré")));
+    }
+
+    #[test]
+    fn uri_template() {
+        assert!(is_valid(
+            "uri-template",
+            json!("http://example.com/dictionary/{term:1}/{term}")
+        ));
+        assert!(is_valid("uri-template", json!("{/list*,path:4}{?x,y}")));
+        assert!(is_valid("uri-template", json!("about")));
+        assert!(!is_valid(
+            "uri-template",
+            json!("http://example.com/dictionary/{term:1}/{term")
+        ));
+        assert!(!is_valid("uri-template", json!("{=reserved}")));
+        assert!(!is_valid("uri-template", json!("{term:10000}")));
+        assert!(!is_valid("uri-template", json!("{a..b}")));
+    }
+
+    #[test]
+    fn json_pointer() {
+        assert!(is_valid("json-pointer", json!("")));
+        assert!(is_valid("json-pointer", json!("/foo/0/a~1b/m~0n")));
+        assert!(!is_valid("json-pointer", json!("foo")));
+        assert!(!is_valid("json-pointer", json!("/foo/~2")));
+        assert!(!is_valid("json-pointer", json!("/foo~")));
+    }
+
+    #[test]
+    fn relative_json_pointer() {
+        assert!(is_valid("relative-json-pointer", json!("0")));
+        assert!(is_valid("relative-json-pointer", json!("1/foo/0")));
+        assert!(is_valid("relative-json-pointer", json!("2#")));
+        assert!(!is_valid("relative-json-pointer", json!("/foo")));
+        assert!(!is_valid("relative-json-pointer", json!("01/foo")));
+        assert!(!is_valid("relative-json-pointer", json!("1#/foo")));
+    }
+
+    #[test]
+    fn regex() {
+        assert!(is_valid("regex", json!("^[a-z]+\\d*$")));
+        assert!(!is_valid("regex", json!("^(abc")));
+    }
+
+    #[test]
+    fn non_strings_and_unknown_formats_pass() {
+        assert!(is_valid("ipv4", json!(42)));
+        assert!(is_valid("unknown-format", json!("anything")));
+    }
+
+    #[test]
+    fn annotate_mode_reports_warnings() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        scope.format_mode(schema::FormatMode::Annotate);
+        let schema = scope
+            .compile_and_return(json!({ "format": "ipv4" }), false)
+            .unwrap();
+
+        let state = schema.validate(&json!("127.0.0.256"));
+        assert!(state.is_valid());
+        assert_eq!(state.warnings.len(), 1);
+        assert!(state.warnings[0].is::<error::Format>());
+        assert!(schema.validate(&json!("127.0.0.1")).warnings.is_empty());
+    }
+
+    #[test]
+    fn annotate_mode_warnings_pass_through_combinators() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        scope.format_mode(schema::FormatMode::Annotate);
+        let mut warnings = |def: OwnedValue| {
+            let schema = scope.compile_and_return(def, false).unwrap();
+            let state = schema.validate(&json!("127.0.0.256"));
+            assert!(state.is_valid());
+            state.warnings.len()
+        };
+
+        assert_eq!(warnings(json!({ "anyOf": [{ "format": "ipv4" }, {}] })), 1);
+        assert_eq!(
+            warnings(json!({ "oneOf": [{ "format": "ipv4" }, { "type": "integer" }] })),
+            1
+        );
+        assert_eq!(
+            warnings(json!({ "if": { "format": "ipv4" }, "then": { "format": "ipv6" } })),
+            2
+        );
+
+        // Only from the branches that are kept
+        assert_eq!(
+            warnings(json!({ "anyOf": [{ "format": "ipv4", "maxLength": 3 }, {}] })),
+            0
+        );
+        assert_eq!(
+            warnings(json!({ "if": { "format": "ipv4", "maxLength": 3 }, "else": {} })),
+            0
+        );
+    }
+
+    #[test]
+    fn mode_follows_the_draft() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let mut asserts = |metaschema: &str| {
+            let schema = scope
+                .compile_and_return(json!({ "$schema": metaschema, "format": "ipv4" }), false)
+                .unwrap();
+            !schema.validate(&json!("127.0.0.256")).is_valid()
+        };
+
+        assert!(asserts("http://json-schema.org/draft-04/schema#"));
+        assert!(asserts("http://json-schema.org/draft-07/schema#"));
+        assert!(!asserts("https://json-schema.org/draft/2019-09/schema"));
+        assert!(!asserts("https://json-schema.org/draft/2020-12/schema"));
+
+        scope.format_mode(schema::FormatMode::Assert);
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "format": "ipv4"
+                }),
+                false,
+            )
+            .unwrap();
+        assert!(!schema.validate(&json!("127.0.0.256")).is_valid());
+    }
+
+    #[test]
+    fn format_assertion_vocabulary() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        scope.format_mode(schema::FormatMode::Annotate);
+        scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "$id": "https://example.com/meta/format-assertion",
+                    "$vocabulary": {
+                        "https://json-schema.org/draft/2020-12/vocab/core": true,
+                        "https://json-schema.org/draft/2020-12/vocab/format-annotation": true,
+                        "https://json-schema.org/draft/2020-12/vocab/format-assertion": true
+                    }
+                }),
+                false,
+            )
+            .unwrap();
+
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://example.com/meta/format-assertion",
+                    "format": "ipv4"
+                }),
+                false,
+            )
+            .unwrap();
+        assert!(!schema.validate(&json!("127.0.0.256")).is_valid());
+        assert!(schema.validate(&json!("127.0.0.1")).is_valid());
+    }
+
+    #[test]
+    fn assert_only_listed_formats() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        scope.format_mode(schema::FormatMode::AssertOnly(
+            vec!["ipv4".to_string()].into_iter().collect(),
+        ));
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "properties": {
+                        "ip": { "format": "ipv4" },
+                        "email": { "format": "email" }
+                    }
+                }),
+                false,
+            )
+            .unwrap();
+
+        assert!(!schema.validate(&json!({ "ip": "127.0.0.256" })).is_valid());
+
+        let state = schema.validate(&json!({ "email": "ernad.example.com" }));
+        assert!(state.is_valid());
+        assert_eq!(state.warnings.len(), 1);
+    }
+
+    #[test]
+    fn format_must_be_a_string() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        assert!(scope
+            .compile_and_return(json!({ "format": 1 }), false)
+            .is_err());
+    }
+}
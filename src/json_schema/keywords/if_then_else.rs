@@ -0,0 +1,139 @@
+use simd_json::value::owned::Value as OwnedValue;
+use value_trait::*;
+
+use super::helpers;
+use super::schema;
+use super::validators;
+
+fn subschema_link(
+    def: &OwnedValue,
+    key: &str,
+    ctx: &schema::WalkContext<'_>,
+) -> Result<Option<schema::Link>, schema::SchemaError> {
+    match def.get(key) {
+        Some(value) if value.is_object() || value.is_bool() => {
+            Ok(Some(ctx.link(helpers::alter_fragment_path(
+                ctx.url.clone(),
+                [ctx.escaped_fragment().as_ref(), key].join("/"),
+            ))))
+        }
+        Some(_) => Err(schema::SchemaError::Malformed {
+            path: ctx.fragment.join("/"),
+            detail: format!("The value of `{}` MUST be an object or a boolean", key),
+        }),
+        None => Ok(None),
+    }
+}
+
+#[allow(missing_copy_implementations)]
+pub struct IfThenElse;
+impl<V> super::Keyword<V> for IfThenElse
+where
+    V: Value
+        + std::clone::Clone
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::fmt::Display
+        + std::marker::Sync
+        + std::marker::Send
+        + std::cmp::PartialEq,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::hash::Hash
+        + Eq
+        + std::convert::AsRef<str>
+        + std::fmt::Debug
+        + std::string::ToString
+        + std::marker::Sync
+        + std::marker::Send,
+{
+    fn compile(
+        &self,
+        def: &OwnedValue,
+        ctx: &schema::WalkContext<'_>,
+    ) -> super::KeywordCompilationResult<V> {
+        // `then` and `else` have no effect without `if`
+        keyword_key_exists!(def, "if");
+
+        let if_ = subschema_link(def, "if", ctx)?.unwrap();
+        let then = subschema_link(def, "then", ctx)?;
+        let else_ = subschema_link(def, "else", ctx)?;
+
+        Ok(Some(Box::new(validators::IfThenElse { if_, then, else_ })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::scope;
+    use simd_json::json;
+    use simd_json::value::owned::Value as OwnedValue;
+
+    #[test]
+    fn applies_then_or_else() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "if": { "properties": { "country": { "const": "US" } } },
+                    "then": { "properties": { "postal_code": { "pattern": "^[0-9]{5}$" } } },
+                    "else": { "properties": { "postal_code": { "pattern": "^[A-Z][0-9][A-Z]" } } }
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema
+            .validate(&json!({ "country": "US", "postal_code": "20500" }))
+            .is_valid());
+        assert!(schema
+            .validate(&json!({ "country": "CA", "postal_code": "K1M 1M4" }))
+            .is_valid());
+
+        let state = schema.validate(&json!({ "country": "US", "postal_code": "K1M 1M4" }));
+        assert_eq!(state.errors.len(), 1);
+        assert_eq!(state.errors[0].get_path(), "/postal_code");
+
+        let state = schema.validate(&json!({ "country": "CA", "postal_code": "20500" }));
+        assert_eq!(state.errors.len(), 1);
+        assert_eq!(state.errors[0].get_path(), "/postal_code");
+    }
+
+    #[test]
+    fn missing_branches_and_boolean_schemas() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(json!({ "if": { "type": "string" }, "then": false }), true)
+            .unwrap();
+
+        assert!(!schema.validate(&json!("a")).is_valid());
+        assert!(schema.validate(&json!(1)).is_valid());
+    }
+
+    #[test]
+    fn then_without_if_is_ignored() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(json!({ "then": false, "else": false }), true)
+            .unwrap();
+
+        assert!(schema.validate(&json!("a")).is_valid());
+    }
+
+    #[test]
+    fn if_annotations_count_when_it_passes() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "if": { "properties": { "kind": { "const": "a" } } },
+                    "then": { "properties": { "a": {} } },
+                    "unevaluatedProperties": false
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!({ "kind": "a", "a": 1 })).is_valid());
+        assert!(!schema.validate(&json!({ "kind": "b" })).is_valid());
+    }
+}
@@ -37,26 +37,27 @@ where
             return Ok(None);
         }
 
-        let items = if maybe_items.is_some() {
-            let items_val = maybe_items.unwrap();
+        let items = if let Some(items_val) = maybe_items {
             Some(if items_val.is_object() || items_val.is_bool() {
-                validators::items::ItemsKind::Schema(helpers::alter_fragment_path(
+                validators::items::ItemsKind::Schema(ctx.link(helpers::alter_fragment_path(
                     ctx.url.clone(),
                     [ctx.escaped_fragment().as_ref(), "items"].join("/"),
-                ))
+                )))
             } else if items_val.is_array() {
                 let mut schemas = vec![];
                 for (idx, item) in items_val.as_array().unwrap().iter().enumerate() {
                     if item.is_object() || item.is_bool() {
-                        schemas.push(helpers::alter_fragment_path(
-                            ctx.url.clone(),
-                            [
-                                ctx.escaped_fragment().as_ref(),
-                                "items",
-                                idx.to_string().as_ref(),
-                            ]
-                            .join("/"),
-                        ))
+                        schemas.push(
+                            ctx.link(helpers::alter_fragment_path(
+                                ctx.url.clone(),
+                                [
+                                    ctx.escaped_fragment().as_ref(),
+                                    "items",
+                                    idx.to_string().as_ref(),
+                                ]
+                                .join("/"),
+                            )),
+                        )
                     } else {
                         return Err(schema::SchemaError::Malformed {
                             path: ctx.fragment.join("/"),
@@ -76,15 +77,14 @@ where
             None
         };
 
-        let additional_items = if maybe_additional.is_some() {
-            let additional_val = maybe_additional.unwrap();
+        let additional_items = if let Some(additional_val) = maybe_additional {
             Some(if additional_val.is_bool() {
                 validators::items::AdditionalKind::Boolean(additional_val.as_bool().unwrap())
             } else if additional_val.is_object() {
-                validators::items::AdditionalKind::Schema(helpers::alter_fragment_path(
+                validators::items::AdditionalKind::Schema(ctx.link(helpers::alter_fragment_path(
                     ctx.url.clone(),
                     [ctx.escaped_fragment().as_ref(), "additionalItems"].join("/"),
-                ))
+                )))
             } else {
                 return Err(schema::SchemaError::Malformed {
                     path: ctx.fragment.join("/"),
@@ -101,3 +101,202 @@ where
         })))
     }
 }
+
+/// `prefixItems` and `items` as introduced by draft 2020-12, where `items` only applies to the
+/// elements not covered by `prefixItems`.
+#[allow(missing_copy_implementations)]
+pub struct PrefixItems;
+impl<V> super::Keyword<V> for PrefixItems
+where
+    V: Value
+        + std::clone::Clone
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::fmt::Display
+        + std::marker::Sync
+        + std::marker::Send
+        + std::cmp::PartialEq,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::hash::Hash
+        + Eq
+        + std::convert::AsRef<str>
+        + std::fmt::Debug
+        + std::string::ToString
+        + std::marker::Sync
+        + std::marker::Send,
+{
+    fn compile(
+        &self,
+        def: &OwnedValue,
+        ctx: &schema::WalkContext<'_>,
+    ) -> super::KeywordCompilationResult<V> {
+        let maybe_prefix = def.get("prefixItems");
+        let maybe_items = def.get("items");
+
+        if !(maybe_prefix.is_some() || maybe_items.is_some()) {
+            return Ok(None);
+        }
+
+        let prefix = if let Some(prefix_val) = maybe_prefix {
+            if !prefix_val.is_array() || prefix_val.as_array().unwrap().is_empty() {
+                return Err(schema::SchemaError::Malformed {
+                    path: ctx.fragment.join("/"),
+                    detail: "`prefixItems` must be a non-empty array".to_string(),
+                });
+            }
+
+            let mut schemas = vec![];
+            for (idx, item) in prefix_val.as_array().unwrap().iter().enumerate() {
+                if item.is_object() || item.is_bool() {
+                    schemas.push(
+                        ctx.link(helpers::alter_fragment_path(
+                            ctx.url.clone(),
+                            [
+                                ctx.escaped_fragment().as_ref(),
+                                "prefixItems",
+                                idx.to_string().as_ref(),
+                            ]
+                            .join("/"),
+                        )),
+                    )
+                } else {
+                    return Err(schema::SchemaError::Malformed {
+                        path: ctx.fragment.join("/"),
+                        detail: "Items of this array MUST be objects or booleans".to_string(),
+                    });
+                }
+            }
+            Some(schemas)
+        } else {
+            None
+        };
+
+        let items = if let Some(items_val) = maybe_items {
+            if items_val.is_bool() {
+                Some(validators::items::AdditionalKind::Boolean(
+                    items_val.as_bool().unwrap(),
+                ))
+            } else if items_val.is_object() {
+                Some(validators::items::AdditionalKind::Schema(ctx.link(
+                    helpers::alter_fragment_path(
+                        ctx.url.clone(),
+                        [ctx.escaped_fragment().as_ref(), "items"].join("/"),
+                    ),
+                )))
+            } else {
+                return Err(schema::SchemaError::Malformed {
+                    path: ctx.fragment.join("/"),
+                    detail: "`items` must be an object or a boolean".to_string(),
+                });
+            }
+        } else {
+            None
+        };
+
+        // Without `prefixItems`, `items` covers the whole array, which is exactly what an empty
+        // prefix followed by the additional items schema does.
+        Ok(Some(Box::new(validators::Items {
+            items: Some(validators::items::ItemsKind::Array(
+                prefix.unwrap_or_default(),
+            )),
+            additional: items,
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::scope;
+    use simd_json::json;
+    use simd_json::value::owned::Value as OwnedValue;
+
+    #[test]
+    fn items_array_with_additional_items_in_draft7() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "http://json-schema.org/draft-07/schema#",
+                    "items": [{ "type": "string" }],
+                    "additionalItems": { "type": "integer" }
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!(["a", 1, 2])).is_valid());
+        assert!(!schema.validate(&json!([1])).is_valid());
+        assert!(!schema.validate(&json!(["a", "b"])).is_valid());
+    }
+
+    #[test]
+    fn prefix_items_with_items_as_rest() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "prefixItems": [{ "type": "string" }, { "type": "boolean" }],
+                    "items": { "type": "integer" }
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!(["a", true, 1, 2])).is_valid());
+        assert!(schema.validate(&json!(["a"])).is_valid());
+        assert!(!schema.validate(&json!([true])).is_valid());
+
+        let state = schema.validate(&json!(["a", true, 1, "b"]));
+        assert_eq!(state.errors.len(), 1);
+        assert_eq!(state.errors[0].get_path(), "/3");
+    }
+
+    #[test]
+    fn items_false_closes_the_tuple() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "prefixItems": [{ "type": "string" }],
+                    "items": false
+                }),
+                false,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!(["a"])).is_valid());
+        assert!(!schema.validate(&json!(["a", "b"])).is_valid());
+    }
+
+    #[test]
+    fn items_applies_to_all_elements_without_prefix_items() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "items": { "type": "integer" }
+                }),
+                false,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!([1, 2])).is_valid());
+        assert!(!schema.validate(&json!([1, "a"])).is_valid());
+    }
+
+    #[test]
+    fn items_array_is_rejected_in_draft2020_12() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        assert!(scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "items": [{ "type": "integer" }]
+                }),
+                false,
+            )
+            .is_err());
+    }
+}
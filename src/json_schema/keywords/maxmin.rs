@@ -16,8 +16,8 @@ macro_rules! kw_minmax {
             fn compile(&self, def: &OwnedValue, ctx: &schema::WalkContext<'_>) -> super::KeywordCompilationResult<V> {
                 let value = keyword_key_exists!(def, $keyword);
 
-                if value.is_f64() {
-                    let value = value.as_f64().unwrap();
+                if value.cast_f64().is_some() {
+                    let value = value.cast_f64().unwrap();
                     Ok(Some(Box::new(validators::$name {
                         number: value
                     })))
@@ -36,3 +36,167 @@ kw_minmax!(Maximum, "maximum");
 kw_minmax!(ExclusiveMaximum, "exclusiveMaximum");
 kw_minmax!(Minimum, "minimum");
 kw_minmax!(ExclusiveMinimum, "exclusiveMinimum");
+
+// Draft-04 `exclusiveMaximum`/`exclusiveMinimum` are booleans that make `maximum`/`minimum` exclusive
+macro_rules! kw_minmax_draft4 {
+    ($name:ident, $keyword:expr, $exclusive_keyword:expr, $validator:ident, $exclusive_validator:ident) => {
+        #[allow(missing_copy_implementations)]
+        pub struct $name;
+        impl<V> super::Keyword<V> for $name
+        where
+            V: Value
+                + std::clone::Clone
+                + std::convert::From<simd_json::value::owned::Value>
+                + std::fmt::Display,
+            <V as Value>::Key: std::borrow::Borrow<str>
+                + std::hash::Hash
+                + Eq
+                + std::convert::AsRef<str>
+                + std::fmt::Debug
+                + std::string::ToString
+                + std::marker::Sync
+                + std::marker::Send,
+        {
+            fn compile(
+                &self,
+                def: &OwnedValue,
+                ctx: &schema::WalkContext<'_>,
+            ) -> super::KeywordCompilationResult<V> {
+                let exclusive = def
+                    .get($exclusive_keyword)
+                    .map(|exclusive| exclusive.as_bool());
+                if exclusive.is_some() && exclusive.unwrap().is_none() {
+                    return Err(schema::SchemaError::Malformed {
+                        path: ctx.fragment.join("/"),
+                        detail: format!("the `{}` value must be a boolean", $exclusive_keyword),
+                    });
+                }
+
+                let value = keyword_key_exists!(def, $keyword);
+
+                if value.cast_f64().is_some() {
+                    let number = value.cast_f64().unwrap();
+                    if exclusive.unwrap_or(Some(false)).unwrap() {
+                        Ok(Some(Box::new(validators::$exclusive_validator { number })))
+                    } else {
+                        Ok(Some(Box::new(validators::$validator { number })))
+                    }
+                } else {
+                    Err(schema::SchemaError::Malformed {
+                        path: ctx.fragment.join("/"),
+                        detail: "the `minimum/maximum` value must be a number".to_string(),
+                    })
+                }
+            }
+        }
+    };
+}
+
+kw_minmax_draft4!(
+    Draft4Maximum,
+    "maximum",
+    "exclusiveMaximum",
+    Maximum,
+    ExclusiveMaximum
+);
+kw_minmax_draft4!(
+    Draft4Minimum,
+    "minimum",
+    "exclusiveMinimum",
+    Minimum,
+    ExclusiveMinimum
+);
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::scope;
+    use simd_json::json;
+    use simd_json::value::owned::Value as OwnedValue;
+
+    #[test]
+    fn draft4_boolean_exclusive_maximum() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "http://json-schema.org/draft-04/schema#",
+                    "maximum": 3.0,
+                    "exclusiveMaximum": true
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!(2.5)).is_valid());
+        assert!(!schema.validate(&json!(3.0)).is_valid());
+
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "http://json-schema.org/draft-04/schema#",
+                    "maximum": 3.0,
+                    "exclusiveMaximum": false
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!(3.0)).is_valid());
+    }
+
+    #[test]
+    fn draft4_boolean_exclusive_minimum() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "http://json-schema.org/draft-04/schema#",
+                    "minimum": 1.5,
+                    "exclusiveMinimum": true
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!(2.0)).is_valid());
+        assert!(!schema.validate(&json!(1.5)).is_valid());
+    }
+
+    #[test]
+    fn draft4_exclusive_maximum_must_be_a_boolean() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        assert!(scope
+            .compile_and_return(
+                json!({
+                    "$schema": "http://json-schema.org/draft-04/schema#",
+                    "maximum": 3.0,
+                    "exclusiveMaximum": 3.0
+                }),
+                true,
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn draft7_exclusive_maximum_is_a_number() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(json!({ "exclusiveMaximum": 3.0 }), true)
+            .unwrap();
+
+        assert!(schema.validate(&json!(2.5)).is_valid());
+        assert!(!schema.validate(&json!(3.0)).is_valid());
+    }
+
+    #[test]
+    fn integers() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(json!({ "minimum": 1, "maximum": 3 }), true)
+            .unwrap();
+
+        assert!(schema.validate(&json!(2)).is_valid());
+        assert!(!schema.validate(&json!(0)).is_valid());
+        assert!(!schema.validate(&json!(3.5)).is_valid());
+    }
+}
@@ -30,8 +30,8 @@ macro_rules! kw_minmax_integer {
             ) -> super::KeywordCompilationResult<V> {
                 let length = keyword_key_exists!(def, $keyword);
 
-                if length.is_f64() {
-                    let length_val = length.as_f64().unwrap();
+                if length.cast_f64().is_some() {
+                    let length_val = length.cast_f64().unwrap();
                     if length_val >= 0f64 && length_val.fract() == 0f64 {
                         Ok(Some(Box::new(validators::$name {
                             length: length_val as u64,
@@ -55,3 +55,25 @@ macro_rules! kw_minmax_integer {
 
 kw_minmax_integer!(MaxLength, "maxLength");
 kw_minmax_integer!(MinLength, "minLength");
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::scope;
+    use simd_json::json;
+    use simd_json::value::owned::Value as OwnedValue;
+
+    #[test]
+    fn integers() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(json!({ "minLength": 1, "maxLength": 3 }), true)
+            .unwrap();
+
+        assert!(schema.validate(&json!("abc")).is_valid());
+        assert!(!schema.validate(&json!("")).is_valid());
+        assert!(!schema.validate(&json!("abcd")).is_valid());
+        assert!(scope
+            .compile_and_return(json!({ "maxLength": -1 }), true)
+            .is_err());
+    }
+}
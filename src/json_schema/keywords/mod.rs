@@ -1,5 +1,4 @@
 use simd_json::value::owned::Value as OwnedValue;
-use std::any;
 use std::fmt;
 use std::sync::Arc;
 use value_trait::Value;
@@ -13,13 +12,22 @@ pub type KeywordMap<V> = hashbrown::HashMap<String, Arc<KeywordConsumer<V>>>;
 pub type KeywordCompilationResult<V> =
     Result<Option<validators::BoxedValidator<V>>, schema::SchemaError>;
 
-pub trait Keyword<V>: Send + Sync + any::Any {
+pub trait Keyword<V>: Send + Sync {
     fn compile(&self, src: &OwnedValue, ctx: &schema::WalkContext) -> KeywordCompilationResult<V>;
     fn is_exclusive(&self) -> bool {
         false
     }
 }
 
+impl<V, T> Keyword<V> for T
+where
+    T: Fn(&OwnedValue, &schema::WalkContext) -> KeywordCompilationResult<V> + Send + Sync,
+{
+    fn compile(&self, src: &OwnedValue, ctx: &schema::WalkContext) -> KeywordCompilationResult<V> {
+        self(src, ctx)
+    }
+}
+
 #[derive(Debug)]
 pub struct KeywordConsumer<V>
 where
@@ -50,22 +58,21 @@ impl<V> fmt::Debug for dyn Keyword<V> {
 
 macro_rules! keyword_key_exists {
     ($val:expr, $key:expr) => {{
-        let maybe_val = $val.get($key);
-
-        if maybe_val.is_none() {
-            return Ok(None);
-        } else {
-            maybe_val.unwrap()
+        match $val.get($key) {
+            Some(value) => value,
+            None => return Ok(None),
         }
     }};
 }
 
 pub mod const_;
 pub mod contains;
+pub mod content;
 pub mod dependencies;
+pub mod deprecated;
 pub mod enum_;
-// FIXME: Reimplement without value trait for keyword val
-//pub mod format;
+pub mod format;
+pub mod if_then_else;
 pub mod items;
 #[macro_use]
 pub mod maxmin_length;
@@ -78,12 +85,14 @@ pub mod of;
 pub mod pattern;
 pub mod properties;
 pub mod property_names;
+pub mod read_write_only;
 pub mod ref_;
 pub mod required;
 pub mod type_;
+pub mod unevaluated;
 pub mod unique_items;
 
-pub fn default<'scope, V: 'scope>() -> KeywordMap<V>
+pub fn default<V>() -> KeywordMap<V>
 where
     V: Value
         + std::clone::Clone
@@ -91,7 +100,8 @@ where
         + std::fmt::Display
         + std::marker::Sync
         + std::marker::Send
-        + std::cmp::PartialEq,
+        + std::cmp::PartialEq
+        + 'static,
     <V as Value>::Key: std::borrow::Borrow<str>
         + std::hash::Hash
         + Eq
@@ -122,9 +132,23 @@ where
         &mut map,
     );
     decouple_keyword((vec!["enum".to_string()], Box::new(enum_::Enum)), &mut map);
+    decouple_keyword(
+        (vec!["format".to_string()], Box::new(format::Format::new())),
+        &mut map,
+    );
     decouple_keyword((vec!["not".to_string()], Box::new(not::Not)), &mut map);
     decouple_keyword(
-        (vec!["items".to_string()], Box::new(items::Items)),
+        (
+            vec!["if".to_string(), "then".to_string(), "else".to_string()],
+            Box::new(if_then_else::IfThenElse),
+        ),
+        &mut map,
+    );
+    decouple_keyword(
+        (
+            vec!["items".to_string(), "additionalItems".to_string()],
+            Box::new(items::Items),
+        ),
         &mut map,
     );
     decouple_keyword((vec!["$ref".to_string()], Box::new(ref_::Ref)), &mut map);
@@ -220,6 +244,13 @@ where
         ),
         &mut map,
     );
+    decouple_keyword(
+        (
+            vec!["readOnly".to_string(), "writeOnly".to_string()],
+            Box::new(read_write_only::ReadWriteOnly),
+        ),
+        &mut map,
+    );
     decouple_keyword(
         (
             vec!["uniqueItems".to_string()],
@@ -231,6 +262,421 @@ where
     map
 }
 
+/// Keywords of draft-06, which predates `if`/`then`/`else`, `readOnly` and `writeOnly`.
+pub fn draft6<V>() -> KeywordMap<V>
+where
+    V: Value
+        + std::clone::Clone
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::fmt::Display
+        + std::marker::Sync
+        + std::marker::Send
+        + std::cmp::PartialEq
+        + 'static,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::hash::Hash
+        + Eq
+        + std::convert::AsRef<str>
+        + std::fmt::Debug
+        + std::string::ToString
+        + std::marker::Sync
+        + std::marker::Send,
+{
+    let mut map = default();
+
+    for key in ["if", "then", "else", "readOnly", "writeOnly"].iter() {
+        map.remove(*key);
+    }
+
+    map
+}
+
+/// Keywords of draft-04. A schema is identified by `id` rather than `$id`, `exclusiveMaximum` and
+/// `exclusiveMinimum` are booleans modifying `maximum` and `minimum`, and `const`, `contains`
+/// and `propertyNames` don't exist yet.
+pub fn draft4<V>() -> KeywordMap<V>
+where
+    V: Value
+        + std::clone::Clone
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::fmt::Display
+        + std::marker::Sync
+        + std::marker::Send
+        + std::cmp::PartialEq
+        + 'static,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::hash::Hash
+        + Eq
+        + std::convert::AsRef<str>
+        + std::fmt::Debug
+        + std::string::ToString
+        + std::marker::Sync
+        + std::marker::Send,
+{
+    let mut map = draft6();
+
+    for key in [
+        "const",
+        "contains",
+        "propertyNames",
+        "maximum",
+        "exclusiveMaximum",
+        "minimum",
+        "exclusiveMinimum",
+    ]
+    .iter()
+    {
+        map.remove(*key);
+    }
+
+    // `id` is read while walking the schema, it only has to be consumed here
+    let id = |_: &OwnedValue, _: &schema::WalkContext| Ok(None);
+    decouple_keyword((vec!["id".to_string()], Box::new(id)), &mut map);
+    decouple_keyword(
+        (
+            vec!["maximum".to_string(), "exclusiveMaximum".to_string()],
+            Box::new(maxmin::Draft4Maximum),
+        ),
+        &mut map,
+    );
+    decouple_keyword(
+        (
+            vec!["minimum".to_string(), "exclusiveMinimum".to_string()],
+            Box::new(maxmin::Draft4Minimum),
+        ),
+        &mut map,
+    );
+
+    map
+}
+
+/// Keywords of draft 2019-09. `dependencies` is split into `dependentRequired` and
+/// `dependentSchemas`, `unevaluatedItems`/`unevaluatedProperties` close schemas composed with
+/// `allOf` or `$ref`, `$recursiveRef` extends recursive schemas and `deprecated` reports
+/// warnings.
+pub fn draft2019_09<V>() -> KeywordMap<V>
+where
+    V: Value
+        + std::clone::Clone
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::fmt::Display
+        + std::marker::Sync
+        + std::marker::Send
+        + std::cmp::PartialEq
+        + 'static,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::hash::Hash
+        + Eq
+        + std::convert::AsRef<str>
+        + std::fmt::Debug
+        + std::string::ToString
+        + std::marker::Sync
+        + std::marker::Send,
+{
+    let mut map = default();
+
+    map.remove("dependencies");
+
+    decouple_keyword(
+        (
+            vec!["dependentRequired".to_string()],
+            Box::new(dependencies::DependentRequired),
+        ),
+        &mut map,
+    );
+    decouple_keyword(
+        (
+            vec!["dependentSchemas".to_string()],
+            Box::new(dependencies::DependentSchemas),
+        ),
+        &mut map,
+    );
+    decouple_keyword(
+        (
+            vec!["unevaluatedItems".to_string()],
+            Box::new(unevaluated::UnevaluatedItems),
+        ),
+        &mut map,
+    );
+    decouple_keyword(
+        (
+            vec!["unevaluatedProperties".to_string()],
+            Box::new(unevaluated::UnevaluatedProperties),
+        ),
+        &mut map,
+    );
+    decouple_keyword(
+        (
+            vec!["$recursiveRef".to_string()],
+            Box::new(ref_::RecursiveRef),
+        ),
+        &mut map,
+    );
+    decouple_keyword(
+        (
+            vec!["deprecated".to_string()],
+            Box::new(deprecated::Deprecated),
+        ),
+        &mut map,
+    );
+
+    map
+}
+
+/// Keywords of draft 2020-12. `items` no longer takes an array and applies to the elements after
+/// `prefixItems`, and `$dynamicRef` replaces `$recursiveRef`.
+pub fn draft2020_12<V>() -> KeywordMap<V>
+where
+    V: Value
+        + std::clone::Clone
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::fmt::Display
+        + std::marker::Sync
+        + std::marker::Send
+        + std::cmp::PartialEq
+        + 'static,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::hash::Hash
+        + Eq
+        + std::convert::AsRef<str>
+        + std::fmt::Debug
+        + std::string::ToString
+        + std::marker::Sync
+        + std::marker::Send,
+{
+    let mut map = draft2019_09();
+
+    for key in ["items", "additionalItems", "$recursiveRef"].iter() {
+        map.remove(*key);
+    }
+
+    decouple_keyword(
+        (
+            vec!["prefixItems".to_string(), "items".to_string()],
+            Box::new(items::PrefixItems),
+        ),
+        &mut map,
+    );
+    decouple_keyword(
+        (vec!["$dynamicRef".to_string()], Box::new(ref_::DynamicRef)),
+        &mut map,
+    );
+
+    map
+}
+
+/// Picks the keywords of one vocabulary out of a draft's keyword map.
+fn vocabulary<V>(map: &KeywordMap<V>, keys: &[&str]) -> KeywordMap<V>
+where
+    V: Value,
+{
+    map.iter()
+        .filter(|(key, _)| keys.contains(&key.as_str()))
+        .map(|(key, consumer)| (key.clone(), consumer.clone()))
+        .collect()
+}
+
+/// Keywords of draft 2019-09 grouped by the vocabulary URIs its metaschema lists in `$vocabulary`.
+pub fn vocabularies2019_09<V>() -> Vec<(&'static str, KeywordMap<V>)>
+where
+    V: Value
+        + std::clone::Clone
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::fmt::Display
+        + std::marker::Sync
+        + std::marker::Send
+        + std::cmp::PartialEq
+        + 'static,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::hash::Hash
+        + Eq
+        + std::convert::AsRef<str>
+        + std::fmt::Debug
+        + std::string::ToString
+        + std::marker::Sync
+        + std::marker::Send,
+{
+    let map = draft2019_09();
+
+    vec![
+        (
+            "https://json-schema.org/draft/2019-09/vocab/core",
+            vocabulary(&map, &["$ref", "$recursiveRef"]),
+        ),
+        (
+            "https://json-schema.org/draft/2019-09/vocab/applicator",
+            vocabulary(
+                &map,
+                &[
+                    "additionalItems",
+                    "unevaluatedItems",
+                    "items",
+                    "contains",
+                    "additionalProperties",
+                    "unevaluatedProperties",
+                    "properties",
+                    "patternProperties",
+                    "dependentSchemas",
+                    "propertyNames",
+                    "if",
+                    "then",
+                    "else",
+                    "allOf",
+                    "anyOf",
+                    "oneOf",
+                    "not",
+                ],
+            ),
+        ),
+        (
+            "https://json-schema.org/draft/2019-09/vocab/validation",
+            vocabulary(&map, VALIDATION_VOCABULARY),
+        ),
+        (
+            "https://json-schema.org/draft/2019-09/vocab/format",
+            vocabulary(&map, &["format"]),
+        ),
+        (
+            "https://json-schema.org/draft/2019-09/vocab/content",
+            vocabulary(&map, CONTENT_VOCABULARY),
+        ),
+        (
+            "https://json-schema.org/draft/2019-09/vocab/meta-data",
+            vocabulary(&map, &["deprecated", "readOnly", "writeOnly"]),
+        ),
+    ]
+}
+
+/// `format` asserting whatever the format mode.
+fn format_assertion<V>() -> KeywordMap<V>
+where
+    V: Value
+        + std::clone::Clone
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::fmt::Display
+        + 'static,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::hash::Hash
+        + Eq
+        + std::convert::AsRef<str>
+        + std::fmt::Debug
+        + std::string::ToString
+        + std::marker::Sync
+        + std::marker::Send,
+{
+    let mut map = hashbrown::HashMap::new();
+    decouple_keyword(
+        (
+            vec!["format".to_string()],
+            Box::new(format::Format::new().asserting()),
+        ),
+        &mut map,
+    );
+    map
+}
+
+/// Keywords of draft 2020-12 grouped by the vocabulary URIs its metaschema lists in `$vocabulary`.
+pub fn vocabularies2020_12<V>() -> Vec<(&'static str, KeywordMap<V>)>
+where
+    V: Value
+        + std::clone::Clone
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::fmt::Display
+        + std::marker::Sync
+        + std::marker::Send
+        + std::cmp::PartialEq
+        + 'static,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::hash::Hash
+        + Eq
+        + std::convert::AsRef<str>
+        + std::fmt::Debug
+        + std::string::ToString
+        + std::marker::Sync
+        + std::marker::Send,
+{
+    let map = draft2020_12();
+
+    vec![
+        (
+            "https://json-schema.org/draft/2020-12/vocab/core",
+            vocabulary(&map, &["$ref", "$dynamicRef"]),
+        ),
+        (
+            "https://json-schema.org/draft/2020-12/vocab/applicator",
+            vocabulary(
+                &map,
+                &[
+                    "prefixItems",
+                    "items",
+                    "contains",
+                    "additionalProperties",
+                    "properties",
+                    "patternProperties",
+                    "dependentSchemas",
+                    "propertyNames",
+                    "if",
+                    "then",
+                    "else",
+                    "allOf",
+                    "anyOf",
+                    "oneOf",
+                    "not",
+                ],
+            ),
+        ),
+        (
+            "https://json-schema.org/draft/2020-12/vocab/unevaluated",
+            vocabulary(&map, &["unevaluatedItems", "unevaluatedProperties"]),
+        ),
+        (
+            "https://json-schema.org/draft/2020-12/vocab/validation",
+            vocabulary(&map, VALIDATION_VOCABULARY),
+        ),
+        (
+            "https://json-schema.org/draft/2020-12/vocab/format-annotation",
+            vocabulary(&map, &["format"]),
+        ),
+        (
+            "https://json-schema.org/draft/2020-12/vocab/format-assertion",
+            format_assertion(),
+        ),
+        (
+            "https://json-schema.org/draft/2020-12/vocab/content",
+            vocabulary(&map, CONTENT_VOCABULARY),
+        ),
+        (
+            "https://json-schema.org/draft/2020-12/vocab/meta-data",
+            vocabulary(&map, &["deprecated", "readOnly", "writeOnly"]),
+        ),
+    ]
+}
+
+const VALIDATION_VOCABULARY: &[&str] = &[
+    "type",
+    "const",
+    "enum",
+    "multipleOf",
+    "maximum",
+    "exclusiveMaximum",
+    "minimum",
+    "exclusiveMinimum",
+    "maxLength",
+    "minLength",
+    "pattern",
+    "maxItems",
+    "minItems",
+    "uniqueItems",
+    "maxContains",
+    "minContains",
+    "maxProperties",
+    "minProperties",
+    "required",
+    "dependentRequired",
+];
+
+const CONTENT_VOCABULARY: &[&str] = &["contentEncoding", "contentMediaType", "contentSchema"];
+
 pub fn decouple_keyword<V>(
     keyword_pair: KeywordPair<V>,
     map: &mut hashbrown::HashMap<String, Arc<KeywordConsumer<V>>>,
@@ -238,7 +684,6 @@ pub fn decouple_keyword<V>(
     V: Value,
 {
     let (keys, keyword) = keyword_pair;
-    dbg!(keys.clone());
 
     let consumer = Arc::new(KeywordConsumer {
         keys: keys.clone(),
@@ -28,8 +28,8 @@ where
     ) -> super::KeywordCompilationResult<V> {
         let multiple_of = keyword_key_exists!(def, "multipleOf");
 
-        if multiple_of.is_f64() {
-            let multiple_of = multiple_of.as_f64().unwrap();
+        if multiple_of.cast_f64().is_some() {
+            let multiple_of = multiple_of.cast_f64().unwrap();
             if multiple_of > 0f64 {
                 Ok(Some(Box::new(validators::MultipleOf {
                     number: multiple_of,
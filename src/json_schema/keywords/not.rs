@@ -34,10 +34,10 @@ where
 
         if not.is_object() || not.is_bool() {
             Ok(Some(Box::new(validators::Not {
-                url: helpers::alter_fragment_path(
+                url: ctx.link(helpers::alter_fragment_path(
                     ctx.url.clone(),
                     [ctx.escaped_fragment().as_ref(), "not"].join("/"),
-                ),
+                )),
             })))
         } else {
             Err(schema::SchemaError::Malformed {
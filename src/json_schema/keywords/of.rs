@@ -47,15 +47,17 @@ macro_rules! of_keyword {
                     let mut schemes = vec![];
                     for (idx, scheme) in of.iter().enumerate() {
                         if scheme.is_object() || scheme.is_bool() {
-                            schemes.push(helpers::alter_fragment_path(
-                                ctx.url.clone(),
-                                [
-                                    ctx.escaped_fragment().as_ref(),
-                                    $kw,
-                                    idx.to_string().as_ref(),
-                                ]
-                                .join("/"),
-                            ))
+                            schemes.push(
+                                ctx.link(helpers::alter_fragment_path(
+                                    ctx.url.clone(),
+                                    [
+                                        ctx.escaped_fragment().as_ref(),
+                                        $kw,
+                                        idx.to_string().as_ref(),
+                                    ]
+                                    .join("/"),
+                                )),
+                            )
                         } else {
                             return Err(schema::SchemaError::Malformed {
                                 path: ctx.fragment.join("/"),
@@ -65,7 +67,7 @@ macro_rules! of_keyword {
                         }
                     }
 
-                    Ok(Some(Box::new(validators::$name { schemes: schemes })))
+                    Ok(Some(Box::new(validators::$name { schemes })))
                 } else {
                     Err(schema::SchemaError::Malformed {
                         path: ctx.fragment.join("/"),
@@ -1,7 +1,7 @@
-use regex;
 use simd_json::value::owned::Value as OwnedValue;
 use value_trait::*;
 
+use super::super::regex_engine;
 use super::schema;
 use super::validators;
 
@@ -28,15 +28,30 @@ where
 
         if pattern.is_str() {
             let pattern_val = pattern.as_str().unwrap();
-            match regex::Regex::new(pattern_val) {
-                Ok(re) => Ok(Some(Box::new(validators::Pattern { regex: re }))),
-                Err(err) => Err(schema::SchemaError::Malformed {
+            match ctx.compile_regex(pattern_val) {
+                Ok(regex) => Ok(Some(Box::new(validators::Pattern {
+                    regex,
+                    input_length: ctx.regex_limits.input_length,
+                }))),
+                Err(regex_engine::RegexError::Syntax(err)) => Err(schema::SchemaError::Malformed {
                     path: ctx.fragment.join("/"),
                     detail: format!(
-                        "The value of pattern must be a valid regular expression, but {:?}",
+                        "The value of pattern must be a valid regular expression, but {}",
                         err
                     ),
                 }),
+                Err(regex_engine::RegexError::TooLarge(limit)) => {
+                    Err(schema::SchemaError::RegexTooLarge {
+                        path: ctx.fragment.join("/"),
+                        limit,
+                    })
+                }
+                Err(regex_engine::RegexError::TooLong(limit)) => {
+                    Err(schema::SchemaError::PatternTooLong {
+                        path: ctx.fragment.join("/"),
+                        limit,
+                    })
+                }
             }
         } else {
             Err(schema::SchemaError::Malformed {
@@ -46,3 +61,197 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::error;
+    use super::super::super::regex_engine;
+    use super::super::super::schema;
+    use super::super::super::scope;
+    use simd_json::json;
+    use simd_json::value::owned::Value as OwnedValue;
+
+    #[test]
+    fn ecma_patterns() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "pattern": "^\\d+\\/\\d+$",
+                    "patternProperties": { "^\\w+$": { "type": "string" } }
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!("1/2")).is_valid());
+        assert!(!schema.validate(&json!("١/٢")).is_valid());
+        assert!(!schema.validate(&json!({ "a_1": 1 })).is_valid());
+        assert!(schema.validate(&json!({ "é": 1 })).is_valid());
+    }
+
+    #[test]
+    fn rust_engine() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        scope.regex_engine(regex_engine::RustEngine);
+        let schema = scope
+            .compile_and_return(json!({ "pattern": "^\\d+$" }), true)
+            .unwrap();
+
+        assert!(schema.validate(&json!("١٢٣")).is_valid());
+    }
+
+    #[test]
+    fn regex_limits() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        scope.regex_limits(regex_engine::RegexLimits {
+            size_limit: 1000,
+            pattern_length: Some(12),
+            ..regex_engine::RegexLimits::default()
+        });
+
+        match scope.compile_and_return(json!({ "pattern": "^\\w{1000}$" }), true) {
+            Err(super::schema::SchemaError::RegexTooLarge { limit: 1000, .. }) => (),
+            _ => panic!("Expected a too large pattern"),
+        }
+
+        match scope.compile_and_return(
+            json!({ "patternProperties": { "^[a-z]+[0-9]+$": {} } }),
+            true,
+        ) {
+            Err(super::schema::SchemaError::PatternTooLong { limit: 12, .. }) => (),
+            _ => panic!("Expected a too long pattern"),
+        }
+    }
+
+    #[test]
+    fn input_length() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        scope.regex_limits(regex_engine::RegexLimits {
+            input_length: Some(3),
+            ..regex_engine::RegexLimits::default()
+        });
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "pattern": "^a",
+                    "patternProperties": { "^a": {} },
+                    "additionalProperties": false
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!("abc")).is_valid());
+        let state = schema.validate(&json!("abcd"));
+        assert!(state.errors[0].is::<error::PatternInputTooLong>());
+        assert!(schema.validate(&json!({ "abc": 1 })).is_valid());
+        let state = schema.validate(&json!({ "abcd": 1 }));
+        assert_eq!(state.errors.len(), 1);
+        assert!(state.errors[0].is::<error::PatternInputTooLong>());
+    }
+
+    #[test]
+    fn input_length_of_declared_and_removed_properties() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        scope.regex_limits(regex_engine::RegexLimits {
+            input_length: Some(3),
+            ..regex_engine::RegexLimits::default()
+        });
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "properties": { "abcde": { "type": "integer" } },
+                    "patternProperties": { "^a": {} },
+                    "additionalProperties": false
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!({ "abcde": 1 })).is_valid());
+        let state = schema.validate(&json!({ "abcde": "a" }));
+        assert_eq!(state.errors.len(), 1);
+        assert!(!state.errors[0].is::<error::PatternInputTooLong>());
+
+        // Names that can't be matched aren't additional
+        let mut instance = json!({ "abcd": 1, "b": 1 });
+        let options = schema::NormalizeOptions {
+            remove_additional: schema::RemoveAdditional::All,
+            ..schema::NormalizeOptions::default()
+        };
+        let (state, normalization) = schema.validate_normalized(&mut instance, options);
+        assert_eq!(normalization.removed, vec!["/b"]);
+        assert_eq!(instance, json!({ "abcd": 1 }));
+        assert_eq!(state.errors.len(), 1);
+        assert!(state.errors[0].is::<error::PatternInputTooLong>());
+    }
+
+    #[cfg(feature = "backtracking")]
+    #[test]
+    fn backtrack_limit() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "pattern": "^(a|a)*(?=b)",
+                    "patternProperties": { "^(a|a)*(?=b)": {} },
+                    "additionalProperties": false
+                }),
+                true,
+            )
+            .unwrap();
+
+        let long = "a".repeat(24);
+        let state = schema.validate(&json!(long.clone()));
+        assert!(state.errors[0].is::<error::PatternTooComplex>());
+        let state = schema.validate(&json!({ long: 1 }));
+        assert_eq!(state.errors.len(), 1);
+        assert!(state.errors[0].is::<error::PatternTooComplex>());
+    }
+
+    #[cfg(not(feature = "backtracking"))]
+    #[test]
+    fn lookaround_needs_backtracking() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let result = scope.compile_and_return(json!({ "pattern": "^a(?!b)" }), true);
+
+        match result {
+            Err(super::schema::SchemaError::Malformed { .. }) => (),
+            _ => panic!("Expected a malformed pattern"),
+        }
+    }
+
+    #[cfg(feature = "backtracking")]
+    #[test]
+    fn lookaround() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(json!({ "pattern": "^a(?!b)" }), true)
+            .unwrap();
+
+        assert!(schema.validate(&json!("ac")).is_valid());
+        assert!(!schema.validate(&json!("ab")).is_valid());
+    }
+
+    #[cfg(feature = "backtracking")]
+    #[test]
+    fn long_inputs_of_lookaround() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "pattern": "^(?=a)a*$",
+                    "patternProperties": { "^(?=a)a*$": { "type": "integer" } },
+                    "additionalProperties": false
+                }),
+                true,
+            )
+            .unwrap();
+
+        let long = "a".repeat(10_000);
+        assert!(schema.validate(&json!(long.clone())).is_valid());
+        assert!(schema.validate(&json!({ long.clone(): 1 })).is_valid());
+        assert!(!schema.validate(&json!({ long: "1" })).is_valid());
+    }
+}
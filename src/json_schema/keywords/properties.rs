@@ -1,8 +1,8 @@
-use regex;
 use simd_json::value::owned::Value as OwnedValue;
 use value_trait::*;
 
 use super::super::helpers;
+use super::super::regex_engine;
 use super::schema;
 use super::validators;
 
@@ -39,8 +39,7 @@ where
             return Ok(None);
         }
 
-        let properties = if maybe_properties.is_some() {
-            let properties = maybe_properties.unwrap();
+        let properties = if let Some(properties) = maybe_properties {
             if properties.is_object() {
                 let mut schemes = hashbrown::HashMap::new();
                 let properties = properties.as_object().unwrap();
@@ -48,7 +47,7 @@ where
                     if value.is_object() || value.is_bool() {
                         schemes.insert(
                             key.to_string(),
-                            helpers::alter_fragment_path(
+                            ctx.link(helpers::alter_fragment_path(
                                 ctx.url.clone(),
                                 [
                                     ctx.escaped_fragment().as_ref(),
@@ -56,7 +55,7 @@ where
                                     helpers::encode(key.as_ref()).as_ref(),
                                 ]
                                 .join("/"),
-                            ),
+                            )),
                         );
                     } else {
                         return Err(schema::SchemaError::Malformed {
@@ -77,15 +76,18 @@ where
             hashbrown::HashMap::new()
         };
 
-        let additional_properties = if maybe_additional.is_some() {
-            let additional_val = maybe_additional.unwrap();
+        let additional_properties = if let Some(additional_val) = maybe_additional {
             if additional_val.is_bool() {
-                validators::properties::AdditionalKind::Boolean(additional_val.as_bool().unwrap())
-            } else if additional_val.is_object() {
-                validators::properties::AdditionalKind::Schema(helpers::alter_fragment_path(
-                    ctx.url.clone(),
-                    [ctx.escaped_fragment().as_ref(), "additionalProperties"].join("/"),
+                Some(validators::properties::AdditionalKind::Boolean(
+                    additional_val.as_bool().unwrap(),
                 ))
+            } else if additional_val.is_object() {
+                Some(validators::properties::AdditionalKind::Schema(ctx.link(
+                    helpers::alter_fragment_path(
+                        ctx.url.clone(),
+                        [ctx.escaped_fragment().as_ref(), "additionalProperties"].join("/"),
+                    ),
+                )))
             } else {
                 return Err(schema::SchemaError::Malformed {
                     path: ctx.fragment.join("/"),
@@ -94,32 +96,43 @@ where
                 });
             }
         } else {
-            validators::properties::AdditionalKind::Boolean(true)
+            None
         };
 
-        let patterns = if maybe_pattern.is_some() {
-            let pattern = maybe_pattern.unwrap();
+        let patterns = if let Some(pattern) = maybe_pattern {
             if pattern.is_object() {
                 let pattern = pattern.as_object().unwrap();
                 let mut patterns = vec![];
 
                 for (key, value) in pattern.iter() {
                     if value.is_object() || value.is_bool() {
-                        match regex::Regex::new(key.as_ref()) {
+                        match ctx.compile_regex(key.as_ref()) {
                             Ok(regex) => {
-                                let url = helpers::alter_fragment_path(ctx.url.clone(), [
+                                let url = ctx.link(helpers::alter_fragment_path(ctx.url.clone(), [
                                     ctx.escaped_fragment().as_ref(),
                                     "patternProperties",
                                     helpers::encode(key.as_ref()).as_ref()
-                                ].join("/"));
+                                ].join("/")));
                                 patterns.push((regex, url));
                             },
-                            Err(_) => {
+                            Err(regex_engine::RegexError::Syntax(_)) => {
                                 return Err(schema::SchemaError::Malformed {
                                     path: ctx.fragment.join("/"),
                                     detail: "Each property name of this object SHOULD be a valid regular expression.".to_string()
                                 })
                             }
+                            Err(regex_engine::RegexError::TooLarge(limit)) => {
+                                return Err(schema::SchemaError::RegexTooLarge {
+                                    path: ctx.fragment.join("/"),
+                                    limit,
+                                })
+                            }
+                            Err(regex_engine::RegexError::TooLong(limit)) => {
+                                return Err(schema::SchemaError::PatternTooLong {
+                                    path: ctx.fragment.join("/"),
+                                    limit,
+                                })
+                            }
                         }
                     } else {
                         return Err(schema::SchemaError::Malformed {
@@ -145,6 +158,168 @@ where
             properties,
             additional: additional_properties,
             patterns,
+            input_length: ctx.regex_limits.input_length,
         })))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::schema;
+    use super::super::super::scope;
+    use simd_json::json;
+    use simd_json::value::owned::Value as OwnedValue;
+
+    #[test]
+    fn defaults() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "definitions": {
+                        "point": {
+                            "properties": {
+                                "x": { "default": 0 },
+                                "y": { "default": 0 }
+                            }
+                        }
+                    },
+                    "properties": {
+                        "name": { "type": "string", "default": "unnamed" },
+                        "a/b": { "default": true },
+                        "origin": { "$ref": "#/definitions/point" },
+                        "path": { "items": { "$ref": "#/definitions/point" } },
+                        "options": {
+                            "default": {},
+                            "properties": { "verbose": { "default": false } }
+                        },
+                        "optional": { "type": "number" }
+                    },
+                    "required": ["name"]
+                }),
+                true,
+            )
+            .unwrap();
+
+        let mut instance = json!({
+            "origin": { "x": 1 },
+            "path": [{ "y": 2 }, {}]
+        });
+        let (state, populated) = schema.validate_with_defaults(&mut instance);
+
+        assert!(state.is_valid());
+        assert_eq!(
+            instance,
+            json!({
+                "name": "unnamed",
+                "a/b": true,
+                "origin": { "x": 1, "y": 0 },
+                "path": [{ "x": 0, "y": 2 }, { "x": 0, "y": 0 }],
+                "options": { "verbose": false }
+            })
+        );
+        assert_eq!(
+            populated,
+            vec![
+                "/a~1b",
+                "/name",
+                "/options",
+                "/options/verbose",
+                "/origin/y",
+                "/path/0/x",
+                "/path/1/x",
+                "/path/1/y",
+            ]
+        );
+    }
+
+    #[test]
+    fn defaults_are_validated() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "properties": { "name": { "type": "string", "default": 1 } },
+                    "additionalProperties": { "properties": { "id": { "default": "x" } } }
+                }),
+                true,
+            )
+            .unwrap();
+
+        let mut instance = json!({ "extra": {} });
+        let (state, populated) = schema.validate_with_defaults(&mut instance);
+
+        assert!(!state.is_valid());
+        assert_eq!(populated, vec!["/name", "/extra/id"]);
+        assert_eq!(instance, json!({ "name": 1, "extra": { "id": "x" } }));
+    }
+
+    fn remove_additional(
+        remove_additional: schema::RemoveAdditional,
+        mut instance: OwnedValue,
+    ) -> (OwnedValue, Vec<String>) {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "properties": {
+                        "id": {},
+                        "meta": { "properties": { "a": {} } },
+                        "strict": { "properties": { "a": {} }, "additionalProperties": false }
+                    },
+                    "patternProperties": { "^x-": {} }
+                }),
+                true,
+            )
+            .unwrap();
+        let options = schema::NormalizeOptions {
+            remove_additional,
+            ..schema::NormalizeOptions::default()
+        };
+        let (state, normalization) = schema.validate_normalized(&mut instance, options);
+
+        assert_eq!(
+            state.is_valid(),
+            remove_additional != schema::RemoveAdditional::Never
+        );
+        (instance, normalization.removed)
+    }
+
+    #[test]
+    fn remove_additional_properties() {
+        let instance = json!({
+            "id": 1,
+            "x-trace": 2,
+            "debug": 3,
+            "meta": { "a": 1, "b": 2 },
+            "strict": { "a": 1, "b/c": 2 }
+        });
+
+        let (removed_instance, removed) =
+            remove_additional(schema::RemoveAdditional::Forbidden, instance.clone());
+        assert_eq!(removed, vec!["/strict/b~1c"]);
+        assert_eq!(
+            removed_instance,
+            json!({
+                "id": 1,
+                "x-trace": 2,
+                "debug": 3,
+                "meta": { "a": 1, "b": 2 },
+                "strict": { "a": 1 }
+            })
+        );
+
+        let (removed_instance, removed) =
+            remove_additional(schema::RemoveAdditional::All, instance.clone());
+        assert_eq!(removed, vec!["/debug", "/meta/b", "/strict/b~1c"]);
+        assert_eq!(
+            removed_instance,
+            json!({ "id": 1, "x-trace": 2, "meta": { "a": 1 }, "strict": { "a": 1 } })
+        );
+
+        let (removed_instance, removed) =
+            remove_additional(schema::RemoveAdditional::Never, instance.clone());
+        assert!(removed.is_empty());
+        assert_eq!(removed_instance, instance);
+    }
+}
@@ -34,10 +34,10 @@ where
 
         if property_names.is_object() || property_names.is_bool() {
             Ok(Some(Box::new(validators::PropertyNames {
-                url: helpers::alter_fragment_path(
+                url: ctx.link(helpers::alter_fragment_path(
                     ctx.url.clone(),
                     [ctx.escaped_fragment().as_ref(), "propertyNames"].join("/"),
-                ),
+                )),
             })))
         } else {
             Err(schema::SchemaError::Malformed {
@@ -47,3 +47,24 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::scope;
+    use simd_json::json;
+    use simd_json::value::owned::Value as OwnedValue;
+
+    #[test]
+    fn names_are_validated() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(json!({ "propertyNames": { "maxLength": 3 } }), true)
+            .unwrap();
+
+        assert!(schema.validate(&json!({ "abc": "long value" })).is_valid());
+
+        let state = schema.validate(&json!({ "abcd": 1 }));
+        assert!(!state.is_valid());
+        assert_eq!(state.errors[0].get_path(), "/[abcd]");
+    }
+}
@@ -0,0 +1,187 @@
+use simd_json::value::owned::Value as OwnedValue;
+use value_trait::*;
+
+use super::schema;
+use super::validators;
+
+/// `readOnly` and `writeOnly`, which fail validation in the direction they forbid the value in,
+/// see `ScopedSchema::validate_for`.
+#[allow(missing_copy_implementations)]
+pub struct ReadWriteOnly;
+impl<V> super::Keyword<V> for ReadWriteOnly
+where
+    V: Value
+        + std::clone::Clone
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::fmt::Display
+        + std::marker::Sync
+        + std::marker::Send
+        + std::cmp::PartialEq
+        + 'static,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::hash::Hash
+        + Eq
+        + std::convert::AsRef<str>
+        + std::fmt::Debug
+        + std::string::ToString
+        + std::marker::Sync
+        + std::marker::Send,
+{
+    fn compile(
+        &self,
+        def: &OwnedValue,
+        ctx: &schema::WalkContext<'_>,
+    ) -> super::KeywordCompilationResult<V> {
+        let mut flags = [false, false];
+
+        for (flag, key) in flags.iter_mut().zip(["readOnly", "writeOnly"].iter()) {
+            if let Some(value) = def.get(*key) {
+                if !value.is_bool() {
+                    return Err(schema::SchemaError::Malformed {
+                        path: ctx.fragment.join("/"),
+                        detail: format!("The value of `{}` must be a boolean", key),
+                    });
+                }
+                *flag = value.as_bool().unwrap();
+            }
+        }
+
+        let [read_only, write_only] = flags;
+        if !read_only && !write_only {
+            return Ok(None);
+        }
+
+        Ok(Some(Box::new(validators::ReadWriteOnly {
+            read_only,
+            write_only,
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::schema;
+    use super::super::super::scope;
+    use super::super::super::validators;
+    use simd_json::json;
+    use simd_json::value::owned::Value as OwnedValue;
+    use std::panic;
+
+    struct Panics;
+
+    impl validators::Validator<OwnedValue> for Panics {
+        fn validate(
+            &self,
+            _: &OwnedValue,
+            _: &str,
+            _: &scope::Scope<OwnedValue>,
+        ) -> validators::ValidationState {
+            panic!("The validator panics")
+        }
+    }
+
+    fn compile(scope: &mut scope::Scope<OwnedValue>) -> schema::ScopedSchema<'_, '_, OwnedValue> {
+        scope
+            .compile_and_return(
+                json!({
+                    "properties": {
+                        "id": { "type": "integer", "readOnly": true },
+                        "password": { "type": "string", "writeOnly": true },
+                        "name": { "type": "string" }
+                    }
+                }),
+                true,
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn validate_for_direction() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = compile(&mut scope);
+        let instance = json!({ "id": 1, "password": "secret", "name": "x" });
+
+        assert!(schema.validate(&instance).is_valid());
+
+        let state = schema.validate_for(&instance, schema::Direction::Request);
+        assert_eq!(state.errors.len(), 1);
+        assert_eq!(state.errors[0].get_code(), "read_only");
+        assert_eq!(state.errors[0].get_path(), "/id");
+
+        let state = schema.validate_for(&instance, schema::Direction::Response);
+        assert_eq!(state.errors.len(), 1);
+        assert_eq!(state.errors[0].get_code(), "write_only");
+        assert_eq!(state.errors[0].get_path(), "/password");
+
+        // The direction only lasts for the call
+        assert!(schema.validate(&instance).is_valid());
+    }
+
+    #[test]
+    fn direction_is_restored_after_a_panic() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        scope.add_keyword(
+            &url::Url::parse(schema::Draft::Draft7.metaschema()).unwrap(),
+            vec!["panics".to_string()],
+            |_: &OwnedValue, _: &schema::WalkContext| {
+                Ok(Some(
+                    Box::new(Panics) as validators::BoxedValidator<OwnedValue>
+                ))
+            },
+        );
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "http://json-schema.org/draft-07/schema#",
+                    "properties": {
+                        "id": { "readOnly": true },
+                        "panics": { "panics": true }
+                    }
+                }),
+                false,
+            )
+            .unwrap();
+
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            schema.validate_for(&json!({ "id": 1, "panics": 1 }), schema::Direction::Request)
+        }));
+        assert!(result.is_err());
+        assert!(schema.validate(&json!({ "id": 1 })).is_valid());
+    }
+
+    #[test]
+    fn strip() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = compile(&mut scope);
+
+        let mut instance = json!({ "id": 1, "password": "secret", "name": "x" });
+        let options = schema::NormalizeOptions {
+            strip: Some(schema::Direction::Request),
+            ..schema::NormalizeOptions::default()
+        };
+        let (state, normalization) = schema.validate_normalized(&mut instance, options);
+
+        assert!(state.is_valid());
+        assert_eq!(normalization.stripped, vec!["/id"]);
+        assert_eq!(instance, json!({ "password": "secret", "name": "x" }));
+
+        let mut instance = json!({ "id": 1, "password": "secret", "name": "x" });
+        let options = schema::NormalizeOptions {
+            strip: Some(schema::Direction::Response),
+            ..schema::NormalizeOptions::default()
+        };
+        let (state, normalization) = schema.validate_normalized(&mut instance, options);
+
+        assert!(state.is_valid());
+        assert_eq!(normalization.stripped, vec!["/password"]);
+        assert_eq!(instance, json!({ "id": 1, "name": "x" }));
+    }
+
+    #[test]
+    fn malformed() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let result = scope.compile_and_return(json!({ "readOnly": "yes" }), true);
+
+        assert!(result.is_err());
+    }
+}
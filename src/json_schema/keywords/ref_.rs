@@ -34,7 +34,7 @@ where
                 .base_url(Some(ctx.url))
                 .parse(ref_.as_str().unwrap());
             match url {
-                Ok(url) => Ok(Some(Box::new(validators::Ref { url }))),
+                Ok(url) => Ok(Some(Box::new(validators::Ref { url: ctx.link(url) }))),
                 Err(_) => Err(schema::SchemaError::Malformed {
                     path: ctx.fragment.join("/"),
                     detail: "The value of $ref must be an URI-encoded JSON Pointer".to_string(),
@@ -48,3 +48,357 @@ where
         }
     }
 }
+
+pub struct DynamicRef;
+
+impl<V> super::Keyword<V> for DynamicRef
+where
+    V: Value
+        + std::clone::Clone
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::fmt::Display
+        + std::marker::Sync
+        + std::marker::Send
+        + std::cmp::PartialEq,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::convert::AsRef<str>
+        + std::fmt::Debug
+        + std::string::ToString
+        + std::marker::Sync
+        + std::marker::Send,
+{
+    fn compile(
+        &self,
+        src: &OwnedValue,
+        ctx: &schema::WalkContext,
+    ) -> super::KeywordCompilationResult<V> {
+        let ref_ = keyword_key_exists!(src, "$dynamicRef");
+
+        if let Some(url) = ref_
+            .as_str()
+            .and_then(|ref_| Url::options().base_url(Some(ctx.url)).parse(ref_).ok())
+        {
+            let anchor = url
+                .fragment()
+                .filter(|fragment| !fragment.is_empty() && !fragment.starts_with('/'))
+                .map(|fragment| fragment.to_string());
+            Ok(Some(Box::new(validators::DynamicRef {
+                url: ctx.link(url),
+                anchor,
+            })))
+        } else {
+            Err(schema::SchemaError::Malformed {
+                path: ctx.fragment.join("/"),
+                detail: "The value of $dynamicRef must be an URI reference".to_string(),
+            })
+        }
+    }
+}
+
+pub struct RecursiveRef;
+
+impl<V> super::Keyword<V> for RecursiveRef
+where
+    V: Value
+        + std::clone::Clone
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::fmt::Display
+        + std::marker::Sync
+        + std::marker::Send
+        + std::cmp::PartialEq,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::convert::AsRef<str>
+        + std::fmt::Debug
+        + std::string::ToString
+        + std::marker::Sync
+        + std::marker::Send,
+{
+    fn compile(
+        &self,
+        src: &OwnedValue,
+        ctx: &schema::WalkContext,
+    ) -> super::KeywordCompilationResult<V> {
+        let ref_ = keyword_key_exists!(src, "$recursiveRef");
+
+        if ref_.as_str() == Some("#") {
+            let mut url = ctx.url.clone();
+            url.set_fragment(None);
+            Ok(Some(Box::new(validators::RecursiveRef {
+                url: ctx.link(url),
+            })))
+        } else {
+            Err(schema::SchemaError::Malformed {
+                path: ctx.fragment.join("/"),
+                detail: "The value of $recursiveRef must be \"#\"".to_string(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::scope;
+    use simd_json::json;
+    use simd_json::value::owned::Value as OwnedValue;
+    use url::Url;
+
+    #[test]
+    fn anchor() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "$defs": { "name": { "$anchor": "name", "type": "string" } },
+                    "properties": { "name": { "$ref": "#name" } }
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!({ "name": "a" })).is_valid());
+        assert!(!schema.validate(&json!({ "name": 1 })).is_valid());
+    }
+
+    #[test]
+    fn plain_name_id() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "definitions": { "name": { "$id": "#name", "type": "string" } },
+                    "properties": { "name": { "$ref": "#name" } }
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!({ "name": "a" })).is_valid());
+        assert!(!schema.validate(&json!({ "name": 1 })).is_valid());
+    }
+
+    #[test]
+    fn nested_id_changes_base() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$id": "http://example.com/root.json",
+                    "definitions": {
+                        "other": {
+                            "$id": "other.json",
+                            "definitions": { "int": { "type": "integer" } },
+                            "properties": { "b": { "$ref": "#/definitions/int" } }
+                        }
+                    },
+                    "properties": {
+                        "a": { "$ref": "other.json#/definitions/int" },
+                        "c": { "$ref": "other.json" }
+                    }
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema
+            .validate(&json!({ "a": 1, "c": { "b": 2 } }))
+            .is_valid());
+        assert!(!schema.validate(&json!({ "a": "1" })).is_valid());
+        assert!(!schema.validate(&json!({ "c": { "b": "2" } })).is_valid());
+    }
+
+    #[test]
+    fn draft4_id() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "http://json-schema.org/draft-04/schema#",
+                    "id": "http://example.com/root.json",
+                    "definitions": {
+                        "other": {
+                            "id": "other.json",
+                            "definitions": { "str": { "type": "string" } }
+                        }
+                    },
+                    "properties": { "a": { "$ref": "other.json#/definitions/str" } }
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!({ "a": "1" })).is_valid());
+        assert!(!schema.validate(&json!({ "a": 1 })).is_valid());
+    }
+
+    #[test]
+    fn percent_encoded_pointers() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "properties": {
+                        "a%b": { "type": "integer" },
+                        "a%25b": { "type": "string" },
+                        "c": { "$ref": "#/properties/a%25b" },
+                        "d": { "$ref": "#/properties/a%2525b" }
+                    }
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!({ "c": 1, "d": "1" })).is_valid());
+        assert!(!schema.validate(&json!({ "c": "1" })).is_valid());
+        assert!(!schema.validate(&json!({ "d": 1 })).is_valid());
+    }
+
+    #[test]
+    fn dynamic_ref_extends_recursive_schema() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "$id": "https://example.com/tree",
+                    "$dynamicAnchor": "node",
+                    "type": "object",
+                    "properties": {
+                        "data": true,
+                        "children": { "type": "array", "items": { "$dynamicRef": "#node" } }
+                    }
+                }),
+                true,
+            )
+            .unwrap();
+        scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "$id": "https://example.com/strict-tree",
+                    "$dynamicAnchor": "node",
+                    "$ref": "tree",
+                    "unevaluatedProperties": false
+                }),
+                true,
+            )
+            .unwrap();
+
+        let instance = json!({ "children": [{ "daat": 1 }] });
+
+        let tree = scope
+            .resolve(&Url::parse("https://example.com/tree").unwrap())
+            .unwrap();
+
+        assert!(tree.validate(&instance).is_valid());
+
+        let strict_tree = scope
+            .resolve(&Url::parse("https://example.com/strict-tree").unwrap())
+            .unwrap();
+
+        assert!(!strict_tree.validate(&instance).is_valid());
+        assert!(strict_tree
+            .validate(&json!({ "children": [{ "data": 1 }] }))
+            .is_valid());
+    }
+
+    #[test]
+    fn scopes_track_resources_leading_to_dynamic_refs() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        scope.validate_schemas(true);
+        scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "$id": "https://example.com/plain",
+                    "items": { "$ref": "#" }
+                }),
+                true,
+            )
+            .unwrap();
+
+        let plain = Url::parse("https://example.com/plain").unwrap();
+        let metaschema = Url::parse("https://json-schema.org/draft/2020-12/schema").unwrap();
+        assert!(!scope.tracks_dynamic_scope(&plain));
+        assert!(scope.tracks_dynamic_scope(&metaschema));
+
+        scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2019-09/schema",
+                    "$id": "https://example.com/recursive",
+                    "items": { "$recursiveRef": "#" }
+                }),
+                true,
+            )
+            .unwrap();
+        scope
+            .compile_and_return(
+                json!({
+                    "$id": "https://example.com/leading",
+                    "$ref": "https://example.com/recursive"
+                }),
+                true,
+            )
+            .unwrap();
+
+        let recursive = Url::parse("https://example.com/recursive").unwrap();
+        let leading = Url::parse("https://example.com/leading").unwrap();
+        assert!(scope.tracks_dynamic_scope(&recursive));
+        assert!(scope.tracks_dynamic_scope(&leading));
+        assert!(!scope.tracks_dynamic_scope(&plain));
+    }
+
+    #[test]
+    fn recursive_ref_extends_recursive_schema() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2019-09/schema",
+                    "$id": "https://example.com/tree",
+                    "$recursiveAnchor": true,
+                    "type": "object",
+                    "properties": {
+                        "data": true,
+                        "children": { "type": "array", "items": { "$recursiveRef": "#" } }
+                    }
+                }),
+                true,
+            )
+            .unwrap();
+        let strict_tree = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2019-09/schema",
+                    "$id": "https://example.com/strict-tree",
+                    "$recursiveAnchor": true,
+                    "$ref": "tree",
+                    "unevaluatedProperties": false
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(!strict_tree
+            .validate(&json!({ "children": [{ "daat": 1 }] }))
+            .is_valid());
+        assert!(strict_tree
+            .validate(&json!({ "children": [{ "data": 1 }] }))
+            .is_valid());
+    }
+
+    #[test]
+    fn recursive_ref_must_be_a_hash() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        assert!(scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2019-09/schema",
+                    "$recursiveRef": "#/definitions/a"
+                }),
+                true,
+            )
+            .is_err());
+    }
+}
@@ -29,11 +29,9 @@ where
         let type_ = keyword_key_exists!(def, "type");
 
         if type_.is_str() {
-            let ty = type_.as_str().unwrap().parse().ok();
-
-            if ty.is_some() {
+            if let Ok(ty) = type_.as_str().unwrap().parse() {
                 Ok(Some(Box::new(validators::Type {
-                    item: validators::type_::TypeKind::Single(ty.unwrap()),
+                    item: validators::type_::TypeKind::Single(ty),
                 })))
             } else {
                 Err(schema::SchemaError::Malformed {
@@ -57,9 +55,8 @@ where
             let mut converted_types = vec![];
             for ty in types.iter() {
                 if ty.is_str() {
-                    let converted_ty = ty.as_str().unwrap().parse().ok();
-                    if converted_ty.is_some() {
-                        converted_types.push(converted_ty.unwrap());
+                    if let Ok(converted_ty) = ty.as_str().unwrap().parse() {
+                        converted_types.push(converted_ty);
                     } else {
                         return Err(schema::SchemaError::Malformed {
                             path: ctx.fragment.join("/"),
@@ -86,3 +83,123 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::primitive_types::PrimitiveType;
+    use super::super::super::schema;
+    use super::super::super::scope;
+    use super::super::super::validators;
+    use simd_json::json;
+    use simd_json::value::owned::Value as OwnedValue;
+
+    fn coerce(schema: OwnedValue, mut instance: OwnedValue) -> (OwnedValue, bool, usize) {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope.compile_and_return(schema, true).unwrap();
+        let options = schema::NormalizeOptions {
+            coerce_types: true,
+            ..schema::NormalizeOptions::default()
+        };
+        let (state, normalization) = schema.validate_normalized(&mut instance, options);
+
+        (instance, state.is_valid(), normalization.coerced.len())
+    }
+
+    #[test]
+    fn coerce_scalars() {
+        let integer = json!({ "type": "integer" });
+        assert_eq!(coerce(integer.clone(), json!("42")), (json!(42), true, 1));
+        assert_eq!(
+            coerce(integer.clone(), json!("-1.0")),
+            (json!(-1.0), true, 1)
+        );
+        assert_eq!(coerce(integer.clone(), json!(true)), (json!(1), true, 1));
+        assert_eq!(coerce(integer.clone(), json!(null)), (json!(0), true, 1));
+        assert_eq!(
+            coerce(integer.clone(), json!("4.5")),
+            (json!("4.5"), false, 0)
+        );
+        assert_eq!(
+            coerce(integer.clone(), json!(" 42")),
+            (json!(" 42"), false, 0)
+        );
+        assert_eq!(coerce(integer, json!("+42")), (json!("+42"), false, 0));
+
+        let number = json!({ "type": "number" });
+        assert_eq!(coerce(number.clone(), json!("4.5")), (json!(4.5), true, 1));
+        assert_eq!(coerce(number, json!("NaN")), (json!("NaN"), false, 0));
+
+        let boolean = json!({ "type": "boolean" });
+        assert_eq!(
+            coerce(boolean.clone(), json!("true")),
+            (json!(true), true, 1)
+        );
+        assert_eq!(coerce(boolean.clone(), json!(0)), (json!(false), true, 1));
+        assert_eq!(coerce(boolean, json!("yes")), (json!("yes"), false, 0));
+
+        let string = json!({ "type": "string" });
+        assert_eq!(coerce(string.clone(), json!(null)), (json!(""), true, 1));
+        assert_eq!(coerce(string.clone(), json!(42)), (json!("42"), true, 1));
+        assert_eq!(coerce(string, json!(false)), (json!("false"), true, 1));
+
+        let null = json!({ "type": "null" });
+        assert_eq!(coerce(null.clone(), json!("")), (json!(null), true, 1));
+        assert_eq!(coerce(null, json!("a")), (json!("a"), false, 0));
+    }
+
+    #[test]
+    fn coerce_arrays_and_sets() {
+        let array = json!({ "type": "array", "items": { "type": "integer" } });
+        assert_eq!(coerce(array.clone(), json!("7")), (json!([7]), true, 2));
+        assert_eq!(coerce(array, json!({})), (json!({}), false, 0));
+
+        // The value is left alone when it has one of the types, else the first possible wins
+        let set = json!({ "type": ["boolean", "string"] });
+        assert_eq!(coerce(set.clone(), json!("1")), (json!("1"), true, 0));
+        assert_eq!(coerce(set, json!(1)), (json!(true), true, 1));
+    }
+
+    #[test]
+    fn coerce_nested() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "properties": {
+                        "page": { "type": "integer", "default": "1" },
+                        "tags": { "type": "array" }
+                    }
+                }),
+                true,
+            )
+            .unwrap();
+        let mut instance = json!({ "tags": "a" });
+        let (state, normalization) = schema.validate_normalized(
+            &mut instance,
+            schema::NormalizeOptions {
+                defaults: true,
+                coerce_types: true,
+                ..schema::NormalizeOptions::default()
+            },
+        );
+
+        assert!(state.is_valid());
+        assert_eq!(instance, json!({ "page": 1, "tags": ["a"] }));
+        assert_eq!(normalization.populated, vec!["/page"]);
+        assert_eq!(
+            normalization.coerced,
+            vec![
+                validators::Coercion {
+                    path: "/page".to_string(),
+                    from: PrimitiveType::String,
+                    to: PrimitiveType::Integer,
+                },
+                validators::Coercion {
+                    path: "/tags".to_string(),
+                    from: PrimitiveType::String,
+                    to: PrimitiveType::Array,
+                },
+            ]
+        );
+    }
+}
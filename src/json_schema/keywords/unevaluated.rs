@@ -0,0 +1,285 @@
+use simd_json::value::owned::Value as OwnedValue;
+use value_trait::*;
+
+use super::helpers;
+use super::schema;
+use super::validators;
+
+macro_rules! kw_unevaluated {
+    ($name:ident, $keyword:expr) => {
+        #[allow(missing_copy_implementations)]
+        pub struct $name;
+        impl<V> super::Keyword<V> for $name
+        where
+            V: Value
+                + std::clone::Clone
+                + std::convert::From<simd_json::value::owned::Value>
+                + std::fmt::Display
+                + std::marker::Sync
+                + std::marker::Send
+                + std::cmp::PartialEq,
+            <V as Value>::Key: std::borrow::Borrow<str>
+                + std::hash::Hash
+                + Eq
+                + std::convert::AsRef<str>
+                + std::fmt::Debug
+                + std::string::ToString
+                + std::marker::Sync
+                + std::marker::Send,
+        {
+            fn compile(
+                &self,
+                def: &OwnedValue,
+                ctx: &schema::WalkContext<'_>,
+            ) -> super::KeywordCompilationResult<V> {
+                let unevaluated = keyword_key_exists!(def, $keyword);
+
+                let kind = if unevaluated.is_bool() {
+                    validators::unevaluated::UnevaluatedKind::Boolean(
+                        unevaluated.as_bool().unwrap(),
+                    )
+                } else if unevaluated.is_object() {
+                    validators::unevaluated::UnevaluatedKind::Schema(ctx.link(
+                        helpers::alter_fragment_path(
+                            ctx.url.clone(),
+                            [ctx.escaped_fragment().as_ref(), $keyword].join("/"),
+                        ),
+                    ))
+                } else {
+                    return Err(schema::SchemaError::Malformed {
+                        path: ctx.fragment.join("/"),
+                        detail: format!(
+                            "The value of `{}` must be a boolean or an object.",
+                            $keyword
+                        ),
+                    });
+                };
+
+                Ok(Some(Box::new(validators::$name { kind })))
+            }
+        }
+    };
+}
+
+kw_unevaluated!(UnevaluatedProperties, "unevaluatedProperties");
+kw_unevaluated!(UnevaluatedItems, "unevaluatedItems");
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::scope;
+    use simd_json::json;
+    use simd_json::value::owned::Value as OwnedValue;
+
+    #[test]
+    fn properties_through_all_of() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "allOf": [{ "properties": { "foo": { "type": "string" } } }],
+                    "properties": { "bar": { "type": "string" } },
+                    "unevaluatedProperties": false
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema
+            .validate(&json!({ "foo": "a", "bar": "b" }))
+            .is_valid());
+        assert!(!schema
+            .validate(&json!({ "foo": "a", "baz": "c" }))
+            .is_valid());
+    }
+
+    #[test]
+    fn properties_through_ref() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "$defs": {
+                        "base": { "properties": { "foo": {} }, "patternProperties": { "^x-": {} } }
+                    },
+                    "$ref": "#/$defs/base",
+                    "unevaluatedProperties": false
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!({ "foo": 1, "x-bar": 2 })).is_valid());
+        assert!(!schema.validate(&json!({ "foo": 1, "bar": 2 })).is_valid());
+    }
+
+    #[test]
+    fn only_valid_any_of_branches_count() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "anyOf": [
+                        { "properties": { "foo": { "type": "string" } }, "required": ["foo"] },
+                        { "properties": { "bar": { "type": "string" } }, "required": ["bar"] }
+                    ],
+                    "unevaluatedProperties": false
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema
+            .validate(&json!({ "foo": "a", "bar": "b" }))
+            .is_valid());
+        assert!(schema.validate(&json!({ "foo": "a" })).is_valid());
+        assert!(!schema.validate(&json!({ "foo": "a", "bar": 1 })).is_valid());
+    }
+
+    #[test]
+    fn properties_are_tracked_per_value() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "properties": { "a": { "properties": { "b": {} } }, "c/d": {} },
+                    "unevaluatedProperties": false
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema
+            .validate(&json!({ "a": { "b": 1 }, "c/d": 1 }))
+            .is_valid());
+        assert!(!schema
+            .validate(&json!({ "a": { "b": 1 }, "a/b": 1 }))
+            .is_valid());
+        assert!(!schema
+            .validate(&json!({ "a": { "b": 1 }, "b": 1 }))
+            .is_valid());
+    }
+
+    #[test]
+    fn scopes_know_their_unevaluated() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        scope.validate_schemas(true);
+        scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "properties": { "a": {} }
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(!scope.tracks_evaluated());
+
+        scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "unevaluatedItems": false
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(scope.tracks_evaluated());
+    }
+
+    #[test]
+    fn not_drops_annotations() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "not": { "not": { "properties": { "foo": {} } } },
+                    "unevaluatedProperties": false
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(!schema.validate(&json!({ "foo": 1 })).is_valid());
+    }
+
+    #[test]
+    fn nested_unevaluated_properties_evaluate_everything() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "allOf": [{ "unevaluatedProperties": true }],
+                    "unevaluatedProperties": false
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!({ "foo": 1 })).is_valid());
+    }
+
+    #[test]
+    fn unevaluated_properties_schema() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "properties": { "foo": {} },
+                    "unevaluatedProperties": { "type": "integer" }
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!({ "foo": "a", "bar": 1 })).is_valid());
+        assert!(!schema
+            .validate(&json!({ "foo": "a", "bar": "b" }))
+            .is_valid());
+    }
+
+    #[test]
+    fn items_through_prefix_items_and_contains() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "allOf": [{ "prefixItems": [{ "type": "string" }] }],
+                    "contains": { "type": "boolean" },
+                    "unevaluatedItems": false
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!(["a", true, false])).is_valid());
+        assert!(!schema.validate(&json!(["a", true, 1])).is_valid());
+    }
+
+    #[test]
+    fn items_in_nested_arrays_are_tracked_per_location() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "prefixItems": [{ "prefixItems": [{}] }],
+                    "unevaluatedItems": false
+                }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!([[1, 2]])).is_valid());
+        assert!(!schema.validate(&json!([[1, 2], 3])).is_valid());
+    }
+}
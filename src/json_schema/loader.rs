@@ -0,0 +1,265 @@
+//! Reading the schemas of a directory tree, see `Scope::load_directory`.
+
+use simd_json::value::owned::Value as OwnedValue;
+use std::fs;
+use std::io;
+use std::path;
+
+use super::helpers;
+use super::schema;
+
+/// A failure to load one of the schemas of a directory.
+#[derive(Debug)]
+pub enum LoadError {
+    Io {
+        file: path::PathBuf,
+        error: io::Error,
+    },
+    /// The file isn't valid JSON.
+    Parse { file: path::PathBuf },
+    Schema {
+        file: path::PathBuf,
+        error: schema::SchemaError,
+    },
+}
+
+impl LoadError {
+    /// The file the error came from.
+    pub fn file(&self) -> &path::Path {
+        match self {
+            LoadError::Io { file, .. } => file,
+            LoadError::Parse { file } => file,
+            LoadError::Schema { file, .. } => file,
+        }
+    }
+}
+
+/// The `.json` files under `root`, in lexical order. Symbolic links to directories aren't
+/// followed, so a link back up the tree can't recurse forever.
+pub fn json_files(root: &path::Path) -> Result<Vec<path::PathBuf>, LoadError> {
+    let entries = fs::read_dir(root).map_err(|error| LoadError::Io {
+        file: root.to_path_buf(),
+        error,
+    })?;
+
+    let mut paths = vec![];
+    for entry in entries {
+        let entry = entry.map_err(|error| LoadError::Io {
+            file: root.to_path_buf(),
+            error,
+        })?;
+        let file_type = entry.file_type().map_err(|error| LoadError::Io {
+            file: entry.path(),
+            error,
+        })?;
+        paths.push((entry.path(), file_type.is_dir()));
+    }
+    paths.sort();
+
+    let mut files = vec![];
+    for (path, is_dir) in paths {
+        if is_dir {
+            files.extend(json_files(&path)?);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(files)
+}
+
+/// Reads and parses a schema file.
+pub fn read(file: &path::Path) -> Result<OwnedValue, LoadError> {
+    let bytes = fs::read(file).map_err(|error| LoadError::Io {
+        file: file.to_path_buf(),
+        error,
+    })?;
+
+    helpers::parse_json(&bytes).ok_or_else(|| LoadError::Parse {
+        file: file.to_path_buf(),
+    })
+}
+
+/// The URL of `file` under `base`, at the path of `file` relative to `root`, e.g.
+/// `base/common/types.json` for `root/common/types.json`.
+pub fn file_url(base: &url::Url, root: &path::Path, file: &path::Path) -> Option<url::Url> {
+    let relative = file
+        .strip_prefix(root)
+        .ok()?
+        .components()
+        .map(|component| component.as_os_str().to_str())
+        .collect::<Option<Vec<&str>>>()?;
+
+    base.join(&relative.join("/")).ok()
+}
+
+/// A new directory under the temporary directory of the system, removed with what it holds when
+/// it's dropped, so a failing test doesn't leave it behind.
+#[cfg(test)]
+pub(crate) struct TempDir {
+    path: path::PathBuf,
+}
+
+#[cfg(test)]
+impl TempDir {
+    pub fn new(prefix: &str) -> TempDir {
+        let path = std::env::temp_dir().join(format!("{}-{}", prefix, uuid::Uuid::new_v4()));
+        fs::create_dir_all(&path).unwrap();
+        TempDir { path }
+    }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TempDir {
+    type Target = path::Path;
+
+    fn deref(&self) -> &path::Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+impl AsRef<path::Path> for TempDir {
+    fn as_ref(&self) -> &path::Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::schema;
+    use super::super::scope;
+    use super::{LoadError, TempDir};
+    use simd_json::json;
+    use simd_json::value::owned::Value as OwnedValue;
+    use std::fs;
+
+    fn directory(files: &[(&str, &str)]) -> TempDir {
+        let root = TempDir::new("loader");
+        for (file, content) in files.iter() {
+            let file = root.join(file);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, content).unwrap();
+        }
+        root
+    }
+
+    fn base() -> url::Url {
+        url::Url::parse("http://example.com/schemas/").unwrap()
+    }
+
+    #[test]
+    fn loads_in_dependency_order() {
+        let root = directory(&[
+            (
+                "a-user.json",
+                r#"{
+                    "$schema": "http://example.com/schemas/z-meta.json",
+                    "properties": { "id": { "$ref": "common/id.json" } },
+                    "required": ["id"]
+                }"#,
+            ),
+            ("common/id.json", r#"{ "type": "integer" }"#),
+            (
+                "z-meta.json",
+                r#"{
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "$vocabulary": {
+                        "https://json-schema.org/draft/2020-12/vocab/core": true,
+                        "https://json-schema.org/draft/2020-12/vocab/validation": true
+                    }
+                }"#,
+            ),
+            ("notes.txt", "not a schema"),
+        ]);
+
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        scope.ban_unresolved_refs(true);
+        let result = scope.load_directory(&root, &base(), false);
+
+        let ids = result.unwrap();
+        assert_eq!(
+            ids.iter().map(|id| id.as_str()).collect::<Vec<&str>>(),
+            vec![
+                "http://example.com/schemas/z-meta.json",
+                "http://example.com/schemas/a-user.json",
+                "http://example.com/schemas/common/id.json",
+            ]
+        );
+
+        // The metaschema of `a-user.json` leaves out the applicator vocabulary
+        let user = scope.resolve(&ids[1]).unwrap();
+        assert!(user.validate(&json!({ "id": "x" })).is_valid());
+        assert!(!user.validate(&json!({})).is_valid());
+
+        let id = scope.resolve(&ids[2]).unwrap();
+        assert!(!id.validate(&json!("x")).is_valid());
+    }
+
+    #[test]
+    fn relative_ids() {
+        let root = directory(&[("v1/user.json", r#"{ "$id": "person.json" }"#)]);
+
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let result = scope.load_directory(&root, &base(), false);
+
+        assert_eq!(
+            result.unwrap()[0].as_str(),
+            "http://example.com/schemas/v1/person.json"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_directories_are_skipped() {
+        let root = directory(&[("common/id.json", r#"{ "type": "integer" }"#)]);
+        std::os::unix::fs::symlink(&root, root.join("common").join("loop")).unwrap();
+
+        let result = super::json_files(&root);
+
+        assert_eq!(result.unwrap(), vec![root.join("common").join("id.json")]);
+    }
+
+    #[test]
+    fn errors_name_their_file() {
+        let root = directory(&[
+            ("a.json", r#"{ "type": "string" }"#),
+            ("b.json", r#"{ "properties": 1 }"#),
+        ]);
+
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let result = scope.load_directory(&root, &base(), false);
+
+        match result {
+            Err(LoadError::Schema {
+                ref file,
+                error: schema::SchemaError::Malformed { .. },
+            }) => assert_eq!(file, &root.join("b.json")),
+            _ => panic!("expected a malformed schema"),
+        }
+
+        // None of the schemas are kept
+        let a = url::Url::parse("http://example.com/schemas/a.json").unwrap();
+        assert!(scope.resolve(&a).is_none());
+
+        fs::write(root.join("b.json"), "{").unwrap();
+        let result = scope.load_directory(&root, &base(), false);
+
+        match result {
+            Err(ref error @ LoadError::Parse { .. }) => {
+                assert_eq!(error.file(), root.join("b.json").as_path())
+            }
+            _ => panic!("expected a parse error"),
+        }
+    }
+}
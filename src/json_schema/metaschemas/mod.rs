@@ -0,0 +1,37 @@
+//! The metaschemas of the supported drafts, which `Scope::validate_schemas` checks schemas
+//! against. Drafts 2019-09 and 2020-12 split theirs into one metaschema per vocabulary.
+
+use simd_json::value::owned::Value as OwnedValue;
+
+use super::helpers;
+
+static SOURCES: &[&str] = &[
+    include_str!("draft4.json"),
+    include_str!("draft6.json"),
+    include_str!("draft7.json"),
+    include_str!("draft2019_09/schema.json"),
+    include_str!("draft2019_09/core.json"),
+    include_str!("draft2019_09/applicator.json"),
+    include_str!("draft2019_09/validation.json"),
+    include_str!("draft2019_09/meta-data.json"),
+    include_str!("draft2019_09/format.json"),
+    include_str!("draft2019_09/content.json"),
+    include_str!("draft2020_12/schema.json"),
+    include_str!("draft2020_12/core.json"),
+    include_str!("draft2020_12/applicator.json"),
+    include_str!("draft2020_12/unevaluated.json"),
+    include_str!("draft2020_12/validation.json"),
+    include_str!("draft2020_12/meta-data.json"),
+    include_str!("draft2020_12/format-annotation.json"),
+    include_str!("draft2020_12/content.json"),
+];
+
+/// Parses the bundled metaschemas.
+pub fn sources() -> Vec<OwnedValue> {
+    SOURCES
+        .iter()
+        .map(|source| {
+            helpers::parse_json(source.as_bytes()).expect("a bundled metaschema is valid JSON")
+        })
+        .collect()
+}
@@ -2,7 +2,11 @@ pub mod helpers;
 #[macro_use]
 pub mod keywords;
 pub mod error;
+pub mod loader;
+pub mod metaschemas;
 pub mod primitive_types;
+pub mod regex_engine;
+pub mod resolver;
 pub mod schema;
 pub mod scope;
 pub mod validators;
@@ -1,7 +1,7 @@
 use std::fmt;
 use std::str;
 
-#[derive(Copy, Debug, Clone)]
+#[derive(Copy, Debug, Clone, PartialEq, Eq)]
 pub enum PrimitiveType {
     Array,
     Boolean,
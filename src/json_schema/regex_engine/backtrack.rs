@@ -0,0 +1,428 @@
+//! A backtracking matcher over parsed ECMA-262 patterns, for the lookaround assertions and
+//! backreferences the `regex` crate doesn't support.
+//!
+//! Patterns compile to a program of instructions that runs on an explicit stack of points to
+//! backtrack to, so neither the length of the text nor the iterations of a loop grow the call
+//! stack; only the lookarounds nested in the pattern do.
+
+use regex;
+use std::mem;
+use std::sync::Arc;
+
+use super::ecma;
+use super::{MatchError, RegexError, RegexLimits};
+
+type Captures = Vec<Option<(usize, usize)>>;
+
+#[derive(Debug, Clone)]
+enum Inst {
+    Match,
+    Literal(char),
+    Any,
+    // Character classes are matched by the `regex` crate, one character at a time
+    Set(regex::Regex),
+    Start,
+    End,
+    WordBoundary {
+        negated: bool,
+    },
+    // Continues at the first instruction, and at the second when backtracking
+    Split(usize, usize),
+    Jump(usize),
+    // The start and the end of a capturing group
+    Open(usize),
+    Close(usize),
+    // The start of a loop iteration, and the check that the iteration matched something
+    Mark(usize),
+    Progress(usize),
+    Look {
+        behind: bool,
+        negated: bool,
+        program: Arc<Vec<Inst>>,
+    },
+    BackReference(usize),
+}
+
+fn split(next: usize, skip: usize, greedy: bool) -> Inst {
+    if greedy {
+        Inst::Split(next, skip)
+    } else {
+        Inst::Split(skip, next)
+    }
+}
+
+struct Compiler<'a> {
+    limits: &'a RegexLimits,
+    // Loops so far, each with a register for the start of its iterations
+    marks: usize,
+    // Instructions a program may have within `RegexLimits::size_limit`
+    max_len: usize,
+}
+
+impl<'a> Compiler<'a> {
+    // The instructions of `node`, with jumps relative to the first of them
+    fn compile(&mut self, node: &ecma::Node) -> Result<Vec<Inst>, RegexError> {
+        let mut program = vec![];
+
+        match node {
+            ecma::Node::Empty => (),
+            ecma::Node::Literal(c) => program.push(Inst::Literal(*c)),
+            ecma::Node::Any => program.push(Inst::Any),
+            ecma::Node::Class(class) => program.push(Inst::Set(super::build(
+                &format!("\\A{}\\z", ecma::translate_class(class)),
+                self.limits,
+            )?)),
+            ecma::Node::Start => program.push(Inst::Start),
+            ecma::Node::End => program.push(Inst::End),
+            ecma::Node::WordBoundary { negated } => {
+                program.push(Inst::WordBoundary { negated: *negated })
+            }
+            ecma::Node::Group { node, index: None } => program = self.compile(node)?,
+            ecma::Node::Group {
+                node,
+                index: Some(index),
+            } => {
+                let group = self.compile(node)?;
+                program.push(Inst::Open(*index));
+                self.append(&mut program, &group)?;
+                program.push(Inst::Close(*index));
+            }
+            ecma::Node::Look {
+                behind,
+                negated,
+                node,
+            } => {
+                let mut look = self.compile(node)?;
+                look.push(Inst::Match);
+                program.push(Inst::Look {
+                    behind: *behind,
+                    negated: *negated,
+                    program: Arc::new(look),
+                });
+            }
+            ecma::Node::BackReference(index) => program.push(Inst::BackReference(*index)),
+            ecma::Node::NamedBackReference(_) => {
+                return Err(RegexError::Syntax(
+                    "Unresolved named backreference".to_string(),
+                ))
+            }
+            ecma::Node::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+            } => {
+                let body = self.compile(node)?;
+                self.repeat(&mut program, &body, *min, *max, *greedy)?;
+            }
+            ecma::Node::Concat(nodes) => {
+                for node in nodes.iter() {
+                    let next = self.compile(node)?;
+                    self.append(&mut program, &next)?;
+                }
+            }
+            ecma::Node::Alternation(nodes) => {
+                // Every alternative but the last splits to the next one, and jumps past the
+                // rest once it matched
+                let mut jumps = vec![];
+                for (i, node) in nodes.iter().enumerate() {
+                    let alternative = self.compile(node)?;
+                    if i + 1 == nodes.len() {
+                        self.append(&mut program, &alternative)?;
+                        break;
+                    }
+
+                    let start = program.len();
+                    program.push(Inst::Split(0, 0));
+                    self.append(&mut program, &alternative)?;
+                    jumps.push(program.len());
+                    program.push(Inst::Jump(0));
+                    program[start] = Inst::Split(start + 1, program.len());
+                }
+
+                let end = program.len();
+                for jump in jumps {
+                    program[jump] = Inst::Jump(end);
+                }
+            }
+        }
+
+        Ok(program)
+    }
+
+    fn repeat(
+        &mut self,
+        program: &mut Vec<Inst>,
+        body: &[Inst],
+        min: u32,
+        max: Option<u32>,
+        greedy: bool,
+    ) -> Result<(), RegexError> {
+        if !body.is_empty() {
+            for _ in 0..min {
+                self.append(program, body)?;
+            }
+        }
+
+        // Once the minimum is reached, an iteration matching the empty string ends the loop
+        let mark = self.marks;
+        self.marks += 1;
+
+        match max {
+            None => {
+                let start = program.len();
+                program.push(Inst::Split(0, 0));
+                program.push(Inst::Mark(mark));
+                self.append(program, body)?;
+                program.push(Inst::Progress(mark));
+                program.push(Inst::Jump(start));
+                program[start] = split(start + 1, program.len(), greedy);
+            }
+            Some(max) => {
+                let mut splits = vec![];
+                for _ in min..max {
+                    splits.push(program.len());
+                    program.push(Inst::Split(0, 0));
+                    program.push(Inst::Mark(mark));
+                    self.append(program, body)?;
+                    program.push(Inst::Progress(mark));
+                }
+
+                let end = program.len();
+                for start in splits {
+                    program[start] = split(start + 1, end, greedy);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn append(&self, program: &mut Vec<Inst>, instructions: &[Inst]) -> Result<(), RegexError> {
+        let base = program.len();
+        program.extend(instructions.iter().map(|inst| match inst {
+            Inst::Split(first, second) => Inst::Split(first + base, second + base),
+            Inst::Jump(target) => Inst::Jump(target + base),
+            inst => inst.clone(),
+        }));
+
+        if program.len() > self.max_len {
+            return Err(RegexError::TooLarge(self.limits.size_limit));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct Backtracking {
+    program: Vec<Inst>,
+    groups: usize,
+    marks: usize,
+    backtrack_limit: Option<usize>,
+    backtrack_depth: Option<usize>,
+}
+
+impl Backtracking {
+    pub fn new(ast: &ecma::Ast, limits: &RegexLimits) -> Result<Backtracking, RegexError> {
+        let mut compiler = Compiler {
+            limits,
+            marks: 0,
+            max_len: limits.size_limit / mem::size_of::<Inst>(),
+        };
+        let mut program = compiler.compile(&ast.node)?;
+        program.push(Inst::Match);
+
+        Ok(Backtracking {
+            program,
+            groups: ast.groups,
+            marks: compiler.marks,
+            backtrack_limit: limits.backtrack_limit,
+            backtrack_depth: limits.backtrack_depth,
+        })
+    }
+
+    pub fn try_match(&self, text: &str) -> Result<bool, MatchError> {
+        let text = text.chars().collect::<Vec<char>>();
+        let mut search = Search {
+            text: &text,
+            backtrack_limit: self.backtrack_limit,
+            backtrack_depth: self.backtrack_depth,
+            steps: 0,
+        };
+
+        // Like `regex::Regex::is_match`, a match may start anywhere
+        for start in 0..=text.len() {
+            let mut registers = Registers {
+                captures: vec![None; self.groups + 1],
+                opens: vec![0; self.groups + 1],
+                marks: vec![0; self.marks],
+            };
+            if search.run(&self.program, start, None, &mut registers)? {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Registers {
+    captures: Captures,
+    // Where the groups being matched started
+    opens: Vec<usize>,
+    // Where the current iteration of each loop started
+    marks: Vec<usize>,
+}
+
+// The points to backtrack to, and the registers to restore on the way there
+enum Frame {
+    Branch { pc: usize, pos: usize },
+    Capture(usize, Option<(usize, usize)>),
+    Open(usize, usize),
+    Mark(usize, usize),
+    Captures(Captures),
+}
+
+struct Search<'a> {
+    text: &'a [char],
+    backtrack_limit: Option<usize>,
+    backtrack_depth: Option<usize>,
+    // Instructions run so far, lookarounds included
+    steps: usize,
+}
+
+impl<'a> Search<'a> {
+    // Whether `program` matches from `pos`, ending at `end` if given, leaving the registers of
+    // the match
+    fn run(
+        &mut self,
+        program: &[Inst],
+        mut pos: usize,
+        end: Option<usize>,
+        registers: &mut Registers,
+    ) -> Result<bool, MatchError> {
+        let text = self.text;
+        let mut stack = vec![];
+        let mut branches = 0;
+        let mut pc = 0;
+
+        loop {
+            self.steps += 1;
+            if let Some(limit) = self.backtrack_limit.filter(|limit| self.steps > *limit) {
+                return Err(MatchError::BacktrackLimit(limit));
+            }
+
+            let next = match &program[pc] {
+                Inst::Match if end.is_none_or(|end| end == pos) => return Ok(true),
+                Inst::Match => None,
+                Inst::Literal(c) => {
+                    (pos < text.len() && text[pos] == *c).then(|| (pc + 1, pos + 1))
+                }
+                Inst::Any => (pos < text.len() && !ecma::LINE_TERMINATORS.contains(&text[pos]))
+                    .then(|| (pc + 1, pos + 1)),
+                Inst::Set(set) => (pos < text.len()
+                    && set.is_match(text[pos].encode_utf8(&mut [0; 4])))
+                .then(|| (pc + 1, pos + 1)),
+                Inst::Start => (pos == 0).then(|| (pc + 1, pos)),
+                Inst::End => (pos == text.len()).then(|| (pc + 1, pos)),
+                Inst::WordBoundary { negated } => {
+                    let before = pos > 0 && ecma::is_word_char(text[pos - 1]);
+                    let after = pos < text.len() && ecma::is_word_char(text[pos]);
+                    ((before != after) != *negated).then(|| (pc + 1, pos))
+                }
+                Inst::Split(first, second) => {
+                    if let Some(limit) = self.backtrack_depth.filter(|limit| branches >= *limit) {
+                        return Err(MatchError::BacktrackDepth(limit));
+                    }
+                    branches += 1;
+                    stack.push(Frame::Branch { pc: *second, pos });
+                    Some((*first, pos))
+                }
+                Inst::Jump(target) => Some((*target, pos)),
+                Inst::Open(index) => {
+                    stack.push(Frame::Open(*index, registers.opens[*index]));
+                    registers.opens[*index] = pos;
+                    Some((pc + 1, pos))
+                }
+                Inst::Close(index) => {
+                    stack.push(Frame::Capture(*index, registers.captures[*index]));
+                    registers.captures[*index] = Some((registers.opens[*index], pos));
+                    Some((pc + 1, pos))
+                }
+                Inst::Mark(index) => {
+                    stack.push(Frame::Mark(*index, registers.marks[*index]));
+                    registers.marks[*index] = pos;
+                    Some((pc + 1, pos))
+                }
+                Inst::Progress(index) => (registers.marks[*index] != pos).then(|| (pc + 1, pos)),
+                Inst::Look {
+                    behind,
+                    negated,
+                    program,
+                } => {
+                    let mut look = registers.clone();
+                    let mut found = false;
+                    if *behind {
+                        for start in (0..=pos).rev() {
+                            look = registers.clone();
+                            if self.run(program, start, Some(pos), &mut look)? {
+                                found = true;
+                                break;
+                            }
+                        }
+                    } else {
+                        found = self.run(program, pos, None, &mut look)?;
+                    }
+
+                    if *negated {
+                        (!found).then(|| (pc + 1, pos))
+                    } else if found {
+                        // Groups captured by a positive lookaround can be referenced after it
+                        let previous = mem::replace(&mut registers.captures, look.captures);
+                        stack.push(Frame::Captures(previous));
+                        Some((pc + 1, pos))
+                    } else {
+                        None
+                    }
+                }
+                Inst::BackReference(index) => match registers.captures[*index] {
+                    // A group that didn't participate matches the empty string
+                    None => Some((pc + 1, pos)),
+                    Some((start, stop)) => {
+                        let len = stop - start;
+                        (pos + len <= text.len() && text[start..stop] == text[pos..pos + len])
+                            .then(|| (pc + 1, pos + len))
+                    }
+                },
+            };
+
+            match next {
+                Some((next_pc, next_pos)) => {
+                    pc = next_pc;
+                    pos = next_pos;
+                }
+                None => loop {
+                    match stack.pop() {
+                        None => return Ok(false),
+                        Some(Frame::Branch {
+                            pc: next_pc,
+                            pos: next_pos,
+                        }) => {
+                            branches -= 1;
+                            pc = next_pc;
+                            pos = next_pos;
+                            break;
+                        }
+                        Some(Frame::Capture(index, previous)) => {
+                            registers.captures[index] = previous
+                        }
+                        Some(Frame::Open(index, previous)) => registers.opens[index] = previous,
+                        Some(Frame::Mark(index, previous)) => registers.marks[index] = previous,
+                        Some(Frame::Captures(previous)) => registers.captures = previous,
+                    }
+                },
+            }
+        }
+    }
+}
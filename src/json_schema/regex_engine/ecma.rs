@@ -0,0 +1,683 @@
+//! Parser for ECMA-262 regular expressions, as `pattern` and `patternProperties` are written, and
+//! their translation to the syntax of the `regex` crate.
+
+use regex;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Empty,
+    Literal(char),
+    // `.`, which doesn't match line terminators
+    Any,
+    Class(Class),
+    Start,
+    End,
+    WordBoundary {
+        negated: bool,
+    },
+    Group {
+        node: Box<Node>,
+        index: Option<usize>,
+    },
+    Look {
+        behind: bool,
+        negated: bool,
+        node: Box<Node>,
+    },
+    BackReference(usize),
+    // Index into the parser's named references, replaced by a `BackReference` after parsing
+    NamedBackReference(usize),
+    Repeat {
+        node: Box<Node>,
+        min: u32,
+        max: Option<u32>,
+        greedy: bool,
+    },
+    Concat(Vec<Node>),
+    Alternation(Vec<Node>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Class {
+    pub negated: bool,
+    pub items: Vec<ClassItem>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClassItem {
+    Range(char, char),
+    Digit { negated: bool },
+    Word { negated: bool },
+    Space { negated: bool },
+    Property { name: String, negated: bool },
+}
+
+#[derive(Debug)]
+pub struct Ast {
+    pub node: Node,
+    // Number of capturing groups
+    pub groups: usize,
+}
+
+// Line terminators, which `.` doesn't match
+pub const LINE_TERMINATORS: &[char] = &['\n', '\r', '\u{2028}', '\u{2029}'];
+
+// WhiteSpace and LineTerminator code points, which `\s` matches
+pub const WHITE_SPACE: &[(char, char)] = &[
+    ('\t', '\r'),
+    (' ', ' '),
+    ('\u{a0}', '\u{a0}'),
+    ('\u{1680}', '\u{1680}'),
+    ('\u{2000}', '\u{200a}'),
+    ('\u{2028}', '\u{2029}'),
+    ('\u{202f}', '\u{202f}'),
+    ('\u{205f}', '\u{205f}'),
+    ('\u{3000}', '\u{3000}'),
+    ('\u{feff}', '\u{feff}'),
+];
+
+pub fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+pub fn parse(pattern: &str) -> Result<Ast, String> {
+    let mut parser = Parser {
+        chars: pattern.chars().collect(),
+        pos: 0,
+        groups: 0,
+        names: vec![],
+        references: vec![],
+    };
+
+    let node = parser.disjunction()?;
+    if parser.pos < parser.chars.len() {
+        return Err(format!("Unmatched ) at position {}", parser.pos));
+    }
+
+    let node = parser.resolve(node)?;
+
+    Ok(Ast {
+        node,
+        groups: parser.groups,
+    })
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    groups: usize,
+    names: Vec<(String, usize)>,
+    // Named backreferences, resolved once all groups are known
+    references: Vec<String>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    fn looking_at(&self, prefix: &str) -> bool {
+        prefix
+            .chars()
+            .enumerate()
+            .all(|(idx, c)| self.chars.get(self.pos + idx) == Some(&c))
+    }
+
+    fn next(&mut self) -> Result<char, String> {
+        match self.peek() {
+            Some(c) => {
+                self.pos += 1;
+                Ok(c)
+            }
+            None => Err("Unexpected end of pattern".to_string()),
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next()? {
+            c if c == expected => Ok(()),
+            c => Err(format!("Expected {:?} but found {:?}", expected, c)),
+        }
+    }
+
+    fn disjunction(&mut self) -> Result<Node, String> {
+        let mut alternatives = vec![self.alternative()?];
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            alternatives.push(self.alternative()?);
+        }
+
+        Ok(if alternatives.len() == 1 {
+            alternatives.pop().unwrap()
+        } else {
+            Node::Alternation(alternatives)
+        })
+    }
+
+    fn alternative(&mut self) -> Result<Node, String> {
+        let mut terms = vec![];
+        while self.peek().is_some() && self.peek() != Some('|') && self.peek() != Some(')') {
+            terms.push(self.term()?);
+        }
+
+        Ok(match terms.len() {
+            0 => Node::Empty,
+            1 => terms.pop().unwrap(),
+            _ => Node::Concat(terms),
+        })
+    }
+
+    fn term(&mut self) -> Result<Node, String> {
+        let atom = match self.next()? {
+            '^' => return Ok(Node::Start),
+            '$' => return Ok(Node::End),
+            '(' => self.group()?,
+            '.' => Node::Any,
+            '[' => Node::Class(self.class()?),
+            '\\' => match self.peek() {
+                Some('b') => {
+                    self.pos += 1;
+                    return Ok(Node::WordBoundary { negated: false });
+                }
+                Some('B') => {
+                    self.pos += 1;
+                    return Ok(Node::WordBoundary { negated: true });
+                }
+                _ => self.atom_escape()?,
+            },
+            c @ '*' | c @ '+' | c @ '?' => return Err(format!("Nothing to repeat before {:?}", c)),
+            '{' if self.quantifier_bounds().is_some() => {
+                return Err("Nothing to repeat before {".to_string())
+            }
+            c => Node::Literal(c),
+        };
+
+        self.quantifier(atom)
+    }
+
+    fn group(&mut self) -> Result<Node, String> {
+        let node = if self.looking_at("?:") {
+            self.pos += 2;
+            Node::Group {
+                node: Box::new(self.disjunction()?),
+                index: None,
+            }
+        } else if self.looking_at("?=") || self.looking_at("?!") {
+            let negated = self.chars[self.pos + 1] == '!';
+            self.pos += 2;
+            Node::Look {
+                behind: false,
+                negated,
+                node: Box::new(self.disjunction()?),
+            }
+        } else if self.looking_at("?<=") || self.looking_at("?<!") {
+            let negated = self.chars[self.pos + 2] == '!';
+            self.pos += 3;
+            Node::Look {
+                behind: true,
+                negated,
+                node: Box::new(self.disjunction()?),
+            }
+        } else if self.looking_at("?<") {
+            self.pos += 2;
+            let name = self.group_name()?;
+            if self.names.iter().any(|(existing, _)| *existing == name) {
+                return Err(format!("Duplicate group name {:?}", name));
+            }
+            self.groups += 1;
+            let index = self.groups;
+            self.names.push((name, index));
+            Node::Group {
+                node: Box::new(self.disjunction()?),
+                index: Some(index),
+            }
+        } else if self.peek() == Some('?') {
+            return Err("Invalid group".to_string());
+        } else {
+            self.groups += 1;
+            let index = self.groups;
+            Node::Group {
+                node: Box::new(self.disjunction()?),
+                index: Some(index),
+            }
+        };
+
+        self.expect(')')?;
+        Ok(node)
+    }
+
+    fn group_name(&mut self) -> Result<String, String> {
+        let mut name = String::new();
+        loop {
+            match self.next()? {
+                '>' if !name.is_empty() => return Ok(name),
+                c if c == '$' || c == '_' || c.is_alphabetic() => name.push(c),
+                c if c.is_numeric() && !name.is_empty() => name.push(c),
+                c => return Err(format!("Invalid character {:?} in group name", c)),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Option<u32> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+
+        if start == self.pos {
+            None
+        } else {
+            // Bounds too large to repeat are capped, no input is that long
+            let digits = self.chars[start..self.pos].iter().collect::<String>();
+            Some(digits.parse().unwrap_or(u32::MAX))
+        }
+    }
+
+    // Parses `{n}`, `{n,}` or `{n,m}` after a `{`, leaving the position untouched
+    fn quantifier_bounds(&mut self) -> Option<(u32, Option<u32>, usize)> {
+        let start = self.pos;
+        let result = (|| {
+            let min = self.number()?;
+            let max = if self.peek() == Some(',') {
+                self.pos += 1;
+                self.number()
+            } else {
+                Some(min)
+            };
+            if self.peek() == Some('}') {
+                Some((min, max, self.pos + 1))
+            } else {
+                None
+            }
+        })();
+        self.pos = start;
+        result
+    }
+
+    fn quantifier(&mut self, atom: Node) -> Result<Node, String> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                self.pos += 1;
+                match self.quantifier_bounds() {
+                    Some((min, max, end)) => {
+                        self.pos = end - 1;
+                        if max.is_some_and(|max| max < min) {
+                            return Err("Numbers out of order in {} quantifier".to_string());
+                        }
+                        (min, max)
+                    }
+                    // A lone `{` is a literal
+                    None => {
+                        self.pos -= 1;
+                        return Ok(atom);
+                    }
+                }
+            }
+            _ => return Ok(atom),
+        };
+        self.pos += 1;
+
+        let greedy = if self.peek() == Some('?') {
+            self.pos += 1;
+            false
+        } else {
+            true
+        };
+
+        if let Node::Look { behind: true, .. } = atom {
+            return Err("A lookbehind can't be repeated".to_string());
+        }
+
+        Ok(Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+            greedy,
+        })
+    }
+
+    fn atom_escape(&mut self) -> Result<Node, String> {
+        match self.peek() {
+            Some(c @ '1'..='9') => {
+                self.pos += 1;
+                let mut index = c.to_digit(10).unwrap() as usize;
+                while let Some(digit) = self.peek().and_then(|c| c.to_digit(10)) {
+                    index = index.saturating_mul(10).saturating_add(digit as usize);
+                    self.pos += 1;
+                }
+                Ok(Node::BackReference(index))
+            }
+            Some('k') => {
+                self.pos += 1;
+                self.expect('<')?;
+                let name = self.group_name()?;
+                self.references.push(name);
+                Ok(Node::NamedBackReference(self.references.len() - 1))
+            }
+            _ => match self.character_escape(false)? {
+                ClassItem::Range(c, _) => Ok(Node::Literal(c)),
+                item => Ok(Node::Class(Class {
+                    negated: false,
+                    items: vec![item],
+                })),
+            },
+        }
+    }
+
+    fn hex(&mut self, digits: usize) -> Result<u32, String> {
+        let mut value = 0;
+        for _ in 0..digits {
+            let digit = self
+                .next()?
+                .to_digit(16)
+                .ok_or_else(|| "Invalid hexadecimal escape".to_string())?;
+            value = value * 16 + digit;
+        }
+        Ok(value)
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let code = if self.peek() == Some('{') {
+            self.pos += 1;
+            let mut code = 0u32;
+            let mut digits = 0;
+            while let Some(digit) = self.peek().and_then(|c| c.to_digit(16)) {
+                code = code.saturating_mul(16).saturating_add(digit);
+                digits += 1;
+                self.pos += 1;
+            }
+            if digits == 0 {
+                return Err("Invalid unicode escape".to_string());
+            }
+            self.expect('}')?;
+            code
+        } else {
+            let code = self.hex(4)?;
+            // A surrogate pair written as two escapes is a single code point
+            if (0xd800..0xdc00).contains(&code) && self.looking_at("\\u") {
+                let start = self.pos;
+                self.pos += 2;
+                match self.hex(4) {
+                    Ok(low) if (0xdc00..0xe000).contains(&low) => {
+                        return Ok(std::char::from_u32(
+                            0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00),
+                        )
+                        .unwrap());
+                    }
+                    _ => self.pos = start,
+                }
+            }
+            code
+        };
+
+        std::char::from_u32(code).ok_or_else(|| format!("\\u{:x} is not a code point", code))
+    }
+
+    fn property(&mut self, negated: bool) -> Result<ClassItem, String> {
+        self.expect('{')?;
+        let mut name = String::new();
+        loop {
+            match self.next()? {
+                '}' if !name.is_empty() => break,
+                c if c.is_ascii_alphanumeric() || c == '_' || c == '=' => name.push(c),
+                c => return Err(format!("Invalid character {:?} in property name", c)),
+            }
+        }
+        Ok(ClassItem::Property { name, negated })
+    }
+
+    // Escapes valid both in and outside of classes; a single character is returned as a range
+    fn character_escape(&mut self, in_class: bool) -> Result<ClassItem, String> {
+        let single = |c: char| Ok(ClassItem::Range(c, c));
+
+        match self.next()? {
+            'd' => Ok(ClassItem::Digit { negated: false }),
+            'D' => Ok(ClassItem::Digit { negated: true }),
+            'w' => Ok(ClassItem::Word { negated: false }),
+            'W' => Ok(ClassItem::Word { negated: true }),
+            's' => Ok(ClassItem::Space { negated: false }),
+            'S' => Ok(ClassItem::Space { negated: true }),
+            'p' => self.property(false),
+            'P' => self.property(true),
+            'f' => single('\u{c}'),
+            'n' => single('\n'),
+            'r' => single('\r'),
+            't' => single('\t'),
+            'v' => single('\u{b}'),
+            'b' if in_class => single('\u{8}'),
+            '-' if in_class => single('-'),
+            'c' => match self.next()? {
+                c if c.is_ascii_alphabetic() => single(((c as u8) % 32) as char),
+                _ => Err("Invalid control escape".to_string()),
+            },
+            '0' if !self.peek().is_some_and(|c| c.is_ascii_digit()) => single('\0'),
+            'x' => {
+                let code = self.hex(2)?;
+                single(std::char::from_u32(code).unwrap())
+            }
+            'u' => {
+                let c = self.unicode_escape()?;
+                single(c)
+            }
+            c if "^$\\.*+?()[]{}|/".contains(c) => single(c),
+            c => Err(format!("\\{} is not a valid escape", c)),
+        }
+    }
+
+    fn class_atom(&mut self) -> Result<ClassItem, String> {
+        match self.next()? {
+            '\\' => self.character_escape(true),
+            c => Ok(ClassItem::Range(c, c)),
+        }
+    }
+
+    fn class(&mut self) -> Result<Class, String> {
+        let negated = if self.peek() == Some('^') {
+            self.pos += 1;
+            true
+        } else {
+            false
+        };
+
+        let mut items = vec![];
+        loop {
+            if self.peek() == Some(']') {
+                self.pos += 1;
+                return Ok(Class { negated, items });
+            }
+
+            let start = self.class_atom()?;
+
+            if self.peek() == Some('-') && !self.looking_at("-]") {
+                self.pos += 1;
+                let end = self.class_atom()?;
+                match (start, end) {
+                    (ClassItem::Range(start, _), ClassItem::Range(end, _)) if start <= end => {
+                        items.push(ClassItem::Range(start, end))
+                    }
+                    (ClassItem::Range(_, _), ClassItem::Range(_, _)) => {
+                        return Err("Range out of order in character class".to_string())
+                    }
+                    _ => return Err("A character class can't bound a range".to_string()),
+                }
+            } else {
+                items.push(start);
+            }
+        }
+    }
+
+    // Replaces named backreferences with group indices and checks every reference has a group
+    fn resolve(&self, node: Node) -> Result<Node, String> {
+        let resolve_box = |node: Box<Node>| self.resolve(*node).map(Box::new);
+        let resolve_all = |nodes: Vec<Node>| {
+            nodes
+                .into_iter()
+                .map(|node| self.resolve(node))
+                .collect::<Result<Vec<Node>, String>>()
+        };
+
+        Ok(match node {
+            Node::BackReference(index) if index > self.groups => {
+                return Err(format!("Backreference to missing group {}", index))
+            }
+            Node::NamedBackReference(reference) => {
+                let name = &self.references[reference];
+                match self.names.iter().find(|(group, _)| group == name) {
+                    Some((_, index)) => Node::BackReference(*index),
+                    None => return Err(format!("Backreference to missing group {:?}", name)),
+                }
+            }
+            Node::Group { node, index } => Node::Group {
+                node: resolve_box(node)?,
+                index,
+            },
+            Node::Look {
+                behind,
+                negated,
+                node,
+            } => Node::Look {
+                behind,
+                negated,
+                node: resolve_box(node)?,
+            },
+            Node::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+            } => Node::Repeat {
+                node: resolve_box(node)?,
+                min,
+                max,
+                greedy,
+            },
+            Node::Concat(nodes) => Node::Concat(resolve_all(nodes)?),
+            Node::Alternation(nodes) => Node::Alternation(resolve_all(nodes)?),
+            node => node,
+        })
+    }
+}
+
+/// Thrown by `translate` for constructs the `regex` crate doesn't support.
+#[derive(Debug)]
+pub struct Unsupported(pub &'static str);
+
+fn push_char(rust: &mut String, c: char) {
+    rust.push_str(&format!("\\x{{{:x}}}", c as u32));
+}
+
+fn push_ranges(rust: &mut String, ranges: &[(char, char)]) {
+    for (start, end) in ranges.iter() {
+        push_char(rust, *start);
+        if start != end {
+            rust.push('-');
+            push_char(rust, *end);
+        }
+    }
+}
+
+pub fn translate_class(class: &Class) -> String {
+    if class.items.is_empty() {
+        // `[]` matches nothing and `[^]` anything
+        return if class.negated {
+            "(?s:.)".to_string()
+        } else {
+            "(?:\\z.)".to_string()
+        };
+    }
+
+    let mut rust = String::from(if class.negated { "[^" } else { "[" });
+    for item in class.items.iter() {
+        let (ranges, negated): (&[(char, char)], bool) = match item {
+            ClassItem::Range(start, end) => {
+                push_ranges(&mut rust, &[(*start, *end)]);
+                continue;
+            }
+            ClassItem::Property { name, negated } => {
+                rust.push_str(if *negated { "\\P{" } else { "\\p{" });
+                rust.push_str(name);
+                rust.push('}');
+                continue;
+            }
+            ClassItem::Digit { negated } => (&[('0', '9')], *negated),
+            ClassItem::Word { negated } => {
+                (&[('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')], *negated)
+            }
+            ClassItem::Space { negated } => (WHITE_SPACE, *negated),
+        };
+
+        if negated {
+            rust.push_str("[^");
+            push_ranges(&mut rust, ranges);
+            rust.push(']');
+        } else {
+            push_ranges(&mut rust, ranges);
+        }
+    }
+    rust.push(']');
+
+    rust
+}
+
+/// Translates a parsed pattern to the syntax of the `regex` crate.
+pub fn translate(node: &Node) -> Result<String, Unsupported> {
+    Ok(match node {
+        Node::Empty => String::new(),
+        Node::Literal(c) => regex::escape(&c.to_string()),
+        Node::Any => translate_class(&Class {
+            negated: true,
+            items: LINE_TERMINATORS
+                .iter()
+                .map(|c| ClassItem::Range(*c, *c))
+                .collect(),
+        }),
+        Node::Class(class) => translate_class(class),
+        Node::Start => "^".to_string(),
+        Node::End => "$".to_string(),
+        Node::WordBoundary { negated: false } => "(?-u:\\b)".to_string(),
+        Node::WordBoundary { negated: true } => "(?-u:\\B)".to_string(),
+        Node::Group {
+            node,
+            index: Some(_),
+        } => format!("({})", translate(node)?),
+        Node::Group { node, index: None } => format!("(?:{})", translate(node)?),
+        Node::Look { .. } => return Err(Unsupported("lookaround assertions")),
+        Node::BackReference(_) | Node::NamedBackReference(_) => {
+            return Err(Unsupported("backreferences"))
+        }
+        Node::Repeat {
+            node,
+            min,
+            max,
+            greedy,
+        } => {
+            let bounds = match max {
+                Some(max) if max == min => format!("{{{}}}", min),
+                Some(max) => format!("{{{},{}}}", min, max),
+                None => format!("{{{},}}", min),
+            };
+            format!(
+                "(?:{}){}{}",
+                translate(node)?,
+                bounds,
+                if *greedy { "" } else { "?" }
+            )
+        }
+        Node::Concat(nodes) => nodes
+            .iter()
+            .map(translate)
+            .collect::<Result<Vec<String>, Unsupported>>()?
+            .join(""),
+        Node::Alternation(nodes) => format!(
+            "(?:{})",
+            nodes
+                .iter()
+                .map(translate)
+                .collect::<Result<Vec<String>, Unsupported>>()?
+                .join("|")
+        ),
+    })
+}
@@ -0,0 +1,350 @@
+//! Regular expression engines for `pattern`, `patternProperties` and the `regex` format.
+//!
+//! JSON Schema patterns are ECMA-262 regular expressions. The default `EcmaEngine` parses them
+//! with that syntax and semantics (`\d` and `\w` are ASCII, `.` stops at line terminators) and
+//! runs them on the `regex` crate. Lookaround assertions and backreferences have no equivalent
+//! there; with the `backtracking` feature they run on a backtracking matcher instead of being
+//! rejected.
+
+use regex;
+use std::fmt;
+
+#[cfg(feature = "backtracking")]
+mod backtrack;
+pub mod ecma;
+
+/// A compiled pattern.
+pub trait Regex: Send + Sync + fmt::Debug {
+    /// Whether the pattern matches anywhere in `text`.
+    fn is_match(&self, text: &str) -> bool;
+
+    /// Like `is_match`, but fails instead of answering where the matcher gives up within
+    /// `RegexLimits`, which only the backtracking matcher does.
+    fn try_match(&self, text: &str) -> Result<bool, MatchError> {
+        Ok(self.is_match(text))
+    }
+}
+
+impl Regex for regex::Regex {
+    fn is_match(&self, text: &str) -> bool {
+        regex::Regex::is_match(self, text)
+    }
+}
+
+// A match given up on counts as none
+#[cfg(feature = "backtracking")]
+impl Regex for backtrack::Backtracking {
+    fn is_match(&self, text: &str) -> bool {
+        backtrack::Backtracking::try_match(self, text).unwrap_or(false)
+    }
+
+    fn try_match(&self, text: &str) -> Result<bool, MatchError> {
+        backtrack::Backtracking::try_match(self, text)
+    }
+}
+
+/// Bounds on the cost of compiling and running the patterns of a schema, for schemas from
+/// untrusted sources.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegexLimits {
+    /// Size in bytes a compiled pattern may take, see `regex::RegexBuilder::size_limit`.
+    pub size_limit: usize,
+    /// Size in bytes of the lazy DFA cache of a pattern, see
+    /// `regex::RegexBuilder::dfa_size_limit`. A pattern outgrowing it runs slower, not fails.
+    pub dfa_size_limit: usize,
+    /// Number of characters a pattern may have.
+    pub pattern_length: Option<usize>,
+    /// Number of characters of the strings tested against `pattern` and of the property names
+    /// tested against `patternProperties`; longer ones fail validation.
+    pub input_length: Option<usize>,
+    /// Number of steps the backtracking matcher may take to match a string, which it can take
+    /// exponentially many of; strings it gives up on fail validation.
+    pub backtrack_limit: Option<usize>,
+    /// Number of points the backtracking matcher may keep to backtrack to at once, a few words
+    /// of memory each. It adds at most one per step, so `backtrack_limit` bounds them as well.
+    pub backtrack_depth: Option<usize>,
+}
+
+impl Default for RegexLimits {
+    // The defaults of the `regex` crate, with no length caps, and backtracking bounded to what
+    // takes a fraction of a second
+    fn default() -> RegexLimits {
+        RegexLimits {
+            size_limit: 10 * (1 << 20),
+            dfa_size_limit: 2 * (1 << 20),
+            pattern_length: None,
+            input_length: None,
+            backtrack_limit: Some(1_000_000),
+            backtrack_depth: None,
+        }
+    }
+}
+
+/// A match the backtracking matcher gave up on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchError {
+    /// It took more than `RegexLimits::backtrack_limit` steps.
+    BacktrackLimit(usize),
+    /// It kept more than `RegexLimits::backtrack_depth` points to backtrack to.
+    BacktrackDepth(usize),
+}
+
+impl fmt::Display for MatchError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchError::BacktrackLimit(limit) => {
+                write!(fmt, "Matching takes more than {} steps", limit)
+            }
+            MatchError::BacktrackDepth(limit) => {
+                write!(
+                    fmt,
+                    "Matching keeps more than {} points to backtrack to",
+                    limit
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RegexError {
+    Syntax(String),
+    /// The compiled pattern is larger than `RegexLimits::size_limit`.
+    TooLarge(usize),
+    /// The pattern is longer than `RegexLimits::pattern_length`.
+    TooLong(usize),
+}
+
+/// Compiles the patterns of a schema.
+pub trait RegexEngine: Send + Sync + fmt::Debug {
+    fn compile(&self, pattern: &str, limits: &RegexLimits) -> Result<Box<dyn Regex>, RegexError>;
+}
+
+fn build(pattern: &str, limits: &RegexLimits) -> Result<regex::Regex, RegexError> {
+    regex::RegexBuilder::new(pattern)
+        .size_limit(limits.size_limit)
+        .dfa_size_limit(limits.dfa_size_limit)
+        .build()
+        .map_err(|err| match err {
+            regex::Error::CompiledTooBig(limit) => RegexError::TooLarge(limit),
+            err => RegexError::Syntax(err.to_string()),
+        })
+}
+
+/// Compiles patterns as `regex` crate syntax, without translating them.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RustEngine;
+
+impl RegexEngine for RustEngine {
+    fn compile(&self, pattern: &str, limits: &RegexLimits) -> Result<Box<dyn Regex>, RegexError> {
+        build(pattern, limits).map(|regex| Box::new(regex) as Box<dyn Regex>)
+    }
+}
+
+/// Compiles ECMA-262 patterns, the default.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EcmaEngine;
+
+impl RegexEngine for EcmaEngine {
+    fn compile(&self, pattern: &str, limits: &RegexLimits) -> Result<Box<dyn Regex>, RegexError> {
+        let ast = ecma::parse(pattern).map_err(RegexError::Syntax)?;
+
+        match ecma::translate(&ast.node) {
+            Ok(translated) => RustEngine.compile(&translated, limits),
+            #[cfg(feature = "backtracking")]
+            Err(_) => backtrack::Backtracking::new(&ast, limits)
+                .map(|regex| Box::new(regex) as Box<dyn Regex>),
+            #[cfg(not(feature = "backtracking"))]
+            Err(ecma::Unsupported(construct)) => Err(RegexError::Syntax(format!(
+                "{} are not supported without the `backtracking` feature",
+                construct
+            ))),
+        }
+    }
+}
+
+/// Runs every ECMA-262 pattern on the backtracking matcher.
+#[cfg(feature = "backtracking")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BacktrackingEngine;
+
+#[cfg(feature = "backtracking")]
+impl RegexEngine for BacktrackingEngine {
+    fn compile(&self, pattern: &str, limits: &RegexLimits) -> Result<Box<dyn Regex>, RegexError> {
+        let ast = ecma::parse(pattern).map_err(RegexError::Syntax)?;
+        backtrack::Backtracking::new(&ast, limits).map(|regex| Box::new(regex) as Box<dyn Regex>)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile(pattern: &str) -> Result<Box<dyn Regex>, RegexError> {
+        EcmaEngine.compile(pattern, &RegexLimits::default())
+    }
+
+    fn is_match(pattern: &str, text: &str) -> bool {
+        compile(pattern).unwrap().is_match(text)
+    }
+
+    #[test]
+    fn ecma_semantics() {
+        assert!(is_match("^\\d+$", "123"));
+        assert!(!is_match("^\\d+$", "١٢٣"));
+        assert!(!is_match("^\\w$", "é"));
+        assert!(is_match("^\\W$", "é"));
+        assert!(is_match("^\\s$", "\u{feff}"));
+        assert!(!is_match("^.$", "\n"));
+        assert!(is_match("^.$", "🐲"));
+        assert!(!is_match("\\bé", "é"));
+        assert!(is_match("é\\ba", "éa"));
+        assert!(!is_match("a$", "a\n"));
+    }
+
+    #[test]
+    fn ecma_syntax() {
+        assert!(is_match("^a\\/b$", "a/b"));
+        assert!(is_match("^\\u0041\\x42\\u{43}$", "ABC"));
+        assert!(is_match("^\\ud83d\\ude00$", "😀"));
+        assert!(is_match("^\\cJ$", "\n"));
+        assert!(is_match("^[\\d-]+$", "1-2"));
+        assert!(is_match("^[^\\D]$", "5"));
+        assert!(is_match("^[\\b]$", "\u{8}"));
+        assert!(is_match("^[&~-]+$", "&~-"));
+        assert!(is_match("^a{,2}$", "a{,2}"));
+        assert!(is_match("^]}$", "]}"));
+        assert!(is_match("^(?<year>\\d{4})$", "2020"));
+        assert!(is_match("^\\p{Letter}+$", "Ωé"));
+        assert!(is_match("^\\p{Script=Greek}$", "Ω"));
+        assert!(!is_match("[]", "a"));
+        assert!(is_match("^[^]$", "\n"));
+        assert!(is_match("^a+?$", "aaa"));
+    }
+
+    #[test]
+    fn ecma_errors() {
+        assert!(compile("\\a").is_err());
+        assert!(compile("(abc").is_err());
+        assert!(compile("abc)").is_err());
+        assert!(compile("*a").is_err());
+        assert!(compile("a{2,1}").is_err());
+        assert!(compile("[z-a]").is_err());
+        assert!(compile("\\2(a)").is_err());
+        assert!(compile("\\k<b>(?<a>x)").is_err());
+        assert!(compile("(?<a>x)(?<a>y)").is_err());
+        assert!(compile("\\p{Unknown}").is_err());
+    }
+
+    #[test]
+    fn rust_engine() {
+        let regex = RustEngine
+            .compile("^\\d+$", &RegexLimits::default())
+            .unwrap();
+        assert!(regex.is_match("١٢٣"));
+        assert!(RustEngine
+            .compile("a(?=b)", &RegexLimits::default())
+            .is_err());
+    }
+
+    #[test]
+    fn limits() {
+        let limits = RegexLimits {
+            size_limit: 1000,
+            ..RegexLimits::default()
+        };
+
+        assert!(EcmaEngine.compile("^\\w{2}$", &limits).is_ok());
+        assert_eq!(
+            EcmaEngine.compile("^\\w{1000}$", &limits).unwrap_err(),
+            RegexError::TooLarge(1000)
+        );
+    }
+
+    #[cfg(not(feature = "backtracking"))]
+    #[test]
+    fn unsupported() {
+        assert!(compile("a(?=b)").is_err());
+        assert!(compile("(a)\\1").is_err());
+    }
+
+    #[cfg(feature = "backtracking")]
+    #[test]
+    fn backtracking() {
+        assert!(is_match("^a(?=b)", "ab"));
+        assert!(!is_match("^a(?=b)", "ac"));
+        assert!(is_match("^a(?!b)", "ac"));
+        assert!(is_match("(?<=\\$)\\d+", "$10"));
+        assert!(!is_match("(?<!\\$)\\b\\d+", "$10"));
+        assert!(is_match("^(a|b)\\1$", "bb"));
+        assert!(!is_match("^(a|b)\\1$", "ab"));
+        assert!(is_match("^(?<q>['\"]).*\\k<q>$", "'x'"));
+        assert!(!is_match("^(?<q>['\"]).*\\k<q>$", "'x\""));
+        assert!(is_match("^(?:(a)|b)\\1c$", "bc"));
+        assert!(is_match("(?=(a+))a*b\\1", "baaabac"));
+    }
+
+    #[cfg(feature = "backtracking")]
+    #[test]
+    fn backtracking_engine() {
+        let is_match = |pattern: &str, text: &str| {
+            BacktrackingEngine
+                .compile(pattern, &RegexLimits::default())
+                .unwrap()
+                .is_match(text)
+        };
+
+        assert!(is_match("^\\d{2,3}$", "123"));
+        assert!(!is_match("^\\d{2,3}$", "1234"));
+        assert!(is_match("^(a*)*b$", "aaab"));
+        assert!(is_match("^(a*?)+$", "aa"));
+        assert!(is_match("^[^\\s]+$", "abc"));
+        assert!(!is_match("^.$", "\r"));
+        assert!(is_match("x|^$", ""));
+    }
+
+    #[cfg(feature = "backtracking")]
+    #[test]
+    fn backtracking_limits() {
+        let regex = BacktrackingEngine
+            .compile("^(a|a)*(?=b)", &RegexLimits::default())
+            .unwrap();
+        assert_eq!(
+            regex.try_match(&"a".repeat(24)),
+            Err(MatchError::BacktrackLimit(1_000_000))
+        );
+        assert_eq!(regex.try_match("aab"), Ok(true));
+
+        let regex = BacktrackingEngine
+            .compile("^(?=a)a*$", &RegexLimits::default())
+            .unwrap();
+        assert_eq!(regex.try_match(&"a".repeat(100_000)), Ok(true));
+        assert_eq!(
+            regex.try_match(&"a".repeat(1_000_000)),
+            Err(MatchError::BacktrackLimit(1_000_000))
+        );
+
+        let regex = BacktrackingEngine
+            .compile(
+                "^(a|a)*(?=b)",
+                &RegexLimits {
+                    backtrack_limit: None,
+                    backtrack_depth: Some(10),
+                    ..RegexLimits::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            regex.try_match(&"a".repeat(24)),
+            Err(MatchError::BacktrackDepth(10))
+        );
+
+        // Counted repetitions take room in the compiled pattern like in the `regex` crate
+        assert_eq!(
+            BacktrackingEngine
+                .compile("^(?=a)a{1000000}$", &RegexLimits::default())
+                .unwrap_err(),
+            RegexError::TooLarge(10 * (1 << 20))
+        );
+    }
+}
@@ -0,0 +1,251 @@
+//! Sources of the schemas `$ref`s point at that haven't been compiled into a `Scope`, see
+//! `Scope::resolver`.
+
+use hashbrown::HashMap;
+use simd_json::value::owned::Value as OwnedValue;
+use std::fmt;
+use std::fs;
+use std::path;
+
+use super::helpers;
+
+/// Provides schema documents by URL.
+pub trait Resolver: Send + Sync {
+    /// Returns the schema document at `url`, which has no fragment, or `None` if it is unknown.
+    fn resolve(&self, url: &url::Url) -> Option<OwnedValue>;
+}
+
+impl<F> Resolver for F
+where
+    F: Fn(&url::Url) -> Option<OwnedValue> + Send + Sync,
+{
+    fn resolve(&self, url: &url::Url) -> Option<OwnedValue> {
+        self(url)
+    }
+}
+
+impl fmt::Debug for dyn Resolver {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("<resolver>")
+    }
+}
+
+/// Resolves the schema documents registered with `insert`.
+#[derive(Debug, Default)]
+pub struct MapResolver {
+    documents: HashMap<String, OwnedValue>,
+}
+
+impl MapResolver {
+    pub fn new() -> MapResolver {
+        MapResolver::default()
+    }
+
+    pub fn insert(&mut self, url: &url::Url, document: OwnedValue) {
+        let (key, _) = helpers::serialize_schema_path(url);
+        self.documents.insert(key, document);
+    }
+}
+
+impl Resolver for MapResolver {
+    fn resolve(&self, url: &url::Url) -> Option<OwnedValue> {
+        let (key, _) = helpers::serialize_schema_path(url);
+        self.documents.get(&key).cloned()
+    }
+}
+
+/// Resolves the URLs under `base` to the files at the same relative path under `root`, e.g.
+/// `base/common/types.json` to `root/common/types.json`.
+#[derive(Debug)]
+pub struct DirectoryResolver {
+    base: url::Url,
+    root: path::PathBuf,
+}
+
+impl DirectoryResolver {
+    pub fn new<P>(base: url::Url, root: P) -> DirectoryResolver
+    where
+        P: Into<path::PathBuf>,
+    {
+        DirectoryResolver {
+            base,
+            root: root.into(),
+        }
+    }
+}
+
+impl Resolver for DirectoryResolver {
+    fn resolve(&self, url: &url::Url) -> Option<OwnedValue> {
+        let relative = url.as_str().strip_prefix(self.base.as_str())?;
+        let mut file = self.root.clone();
+        for segment in relative.split('/').filter(|segment| !segment.is_empty()) {
+            let segment = percent_encoding::percent_decode_str(segment)
+                .decode_utf8()
+                .ok()?;
+
+            // Encoded separators and dot segments would lead out of `root`
+            if segment == "." || segment == ".." || segment.contains('/') || segment.contains('\\')
+            {
+                return None;
+            }
+            file.push(segment.as_ref());
+        }
+
+        // Nor may symbolic links
+        let file = file.canonicalize().ok()?;
+        if !file.starts_with(self.root.canonicalize().ok()?) {
+            return None;
+        }
+
+        helpers::parse_json(&fs::read(file).ok()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::loader::TempDir;
+    use super::super::schema;
+    use super::super::scope;
+    use super::{DirectoryResolver, MapResolver, Resolver};
+    use simd_json::json;
+    use simd_json::value::owned::Value as OwnedValue;
+    use std::fs;
+
+    fn url(url: &str) -> url::Url {
+        url::Url::parse(url).unwrap()
+    }
+
+    #[test]
+    fn map_resolver() {
+        let mut resolver = MapResolver::new();
+        resolver.insert(
+            &url("http://example.com/name.json"),
+            json!({ "type": "string", "maxLength": 3 }),
+        );
+        resolver.insert(
+            &url("http://example.com/person.json"),
+            json!({ "properties": { "name": { "$ref": "name.json" } } }),
+        );
+
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        scope.resolver(resolver);
+        scope.ban_unresolved_refs(true);
+        let schema = scope
+            .compile_and_return(
+                json!({ "items": { "$ref": "http://example.com/person.json" } }),
+                true,
+            )
+            .unwrap();
+
+        let state = schema.validate(&json!([{ "name": "abc" }]));
+        assert!(state.is_valid());
+        assert!(state.missing.is_empty());
+        assert!(!schema.validate(&json!([{ "name": "abcd" }])).is_valid());
+    }
+
+    #[test]
+    fn callback_resolver() {
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        scope.resolver(|url: &url::Url| {
+            if url.as_str() == "http://example.com/defs.json" {
+                Some(json!({ "definitions": { "positive": { "exclusiveMinimum": 0 } } }))
+            } else {
+                None
+            }
+        });
+        let schema = scope
+            .compile_and_return(
+                json!({ "$ref": "http://example.com/defs.json#/definitions/positive" }),
+                true,
+            )
+            .unwrap();
+
+        assert!(schema.validate(&json!(1)).is_valid());
+        assert!(!schema.validate(&json!(0)).is_valid());
+    }
+
+    #[test]
+    fn directory_resolver() {
+        let root = TempDir::new("resolver");
+        fs::create_dir_all(root.join("common")).unwrap();
+        fs::write(
+            root.join("common").join("id.json"),
+            r#"{ "type": "integer", "minimum": 1 }"#,
+        )
+        .unwrap();
+
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        scope.resolver(DirectoryResolver::new(
+            url("http://example.com/schemas/"),
+            root.to_path_buf(),
+        ));
+        scope.ban_unresolved_refs(true);
+        let result = scope
+            .compile_and_return(
+                json!({
+                    "$id": "http://example.com/schemas/user.json",
+                    "properties": { "id": { "$ref": "common/id.json" } }
+                }),
+                true,
+            )
+            .map(|schema| {
+                (
+                    schema.validate(&json!({ "id": 1 })).is_valid(),
+                    schema.validate(&json!({ "id": 0 })).is_valid(),
+                )
+            });
+
+        assert_eq!(result.unwrap(), (true, false));
+    }
+
+    #[test]
+    fn directory_resolver_stays_in_its_directory() {
+        let dir = TempDir::new("resolver");
+        fs::create_dir_all(dir.join("schemas")).unwrap();
+        fs::create_dir_all(dir.join("secret")).unwrap();
+        fs::write(dir.join("schemas").join("a.json"), "{}").unwrap();
+        fs::write(dir.join("secret").join("s.json"), "{}").unwrap();
+
+        let resolver =
+            DirectoryResolver::new(url("http://example.com/schemas/"), dir.join("schemas"));
+        let resolved = [
+            "http://example.com/schemas/a.json",
+            "http://example.com/schemas/..~1secret~1s.json",
+            "http://example.com/schemas/..%2Fsecret%2Fs.json",
+            "http://example.com/schemas/%2E%2E/secret/s.json",
+        ]
+        .iter()
+        .map(|file| resolver.resolve(&url(file)).is_some())
+        .collect::<Vec<bool>>();
+
+        assert_eq!(resolved, vec![true, false, false, false]);
+    }
+
+    #[test]
+    fn unresolved_refs() {
+        let def = json!({
+            "$id": "http://example.com/root.json",
+            "properties": {
+                "a": { "$ref": "http://example.com/missing.json" },
+                "b": { "$ref": "#/definitions/missing" }
+            }
+        });
+
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        let schema = scope.compile_and_return(def.clone(), true).unwrap();
+        let state = schema.validate(&json!({ "a": 1 }));
+        assert!(state.is_valid());
+        assert_eq!(state.missing.len(), 1);
+
+        let mut scope = scope::Scope::<OwnedValue>::new();
+        scope.ban_unresolved_refs(true);
+        match scope.compile_and_return(def.clone(), true) {
+            Err(schema::SchemaError::UnresolvedRef(_)) => (),
+            _ => panic!("expected an unresolved ref"),
+        }
+
+        // The failed schema isn't left in the scope
+        scope.ban_unresolved_refs(false);
+        assert!(scope.compile_and_return(def, true).is_ok());
+    }
+}
@@ -1,11 +1,16 @@
+use super::error;
 use super::helpers;
 use super::keywords;
+use super::regex_engine;
 use super::scope;
 use super::validators;
 
 use phf;
 
+use std::cell;
 use std::collections;
+use std::sync;
+use std::sync::Arc;
 
 use simd_json::value::owned::Value as OwnedValue;
 use value_trait::*;
@@ -19,13 +24,69 @@ where
     schema: Option<url::Url>,
     // JSON that defines schema
     source: OwnedValue,
-    tree: collections::BTreeMap<String, Schema<V>>,
+    tree: collections::BTreeMap<String, Arc<Schema<V>>>,
     validators: validators::Validators<V>,
     scopes: hashbrown::HashMap<String, Vec<String>>,
+    // Subschema URLs of the validators of this schema and its subschemas
+    links: Vec<Link>,
 }
 
 include!(concat!(env!("OUT_DIR"), "/codegen.rs"));
 
+thread_local! {
+    // Schema resources entered during the current validation, outermost first
+    static DYNAMIC_SCOPE: cell::RefCell<Vec<url::Url>> = const { cell::RefCell::new(vec![]) };
+    // Schemas the validation running on this thread is nested in
+    static DEPTH: cell::Cell<usize> = const { cell::Cell::new(0) };
+    // Direction of the validation running on this thread, if any
+    static DIRECTION: cell::Cell<Option<Direction>> = const { cell::Cell::new(None) };
+}
+
+/// Which way an instance travels, for `readOnly` and `writeOnly`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Sent to the owner of the data, so `readOnly` values are errors.
+    Request,
+    /// Sent by the owner of the data, so `writeOnly` values are errors.
+    Response,
+}
+
+/// The direction of the validation running on this thread, see `ScopedSchema::validate_for`.
+pub fn direction() -> Option<Direction> {
+    DIRECTION.with(|direction| direction.get())
+}
+
+// Sets the direction of the validation running on this thread until it's dropped, which restores
+// the direction of the enclosing one even if the validation panics
+struct DirectionGuard {
+    previous: Option<Direction>,
+}
+
+impl DirectionGuard {
+    fn enter(direction: Direction) -> DirectionGuard {
+        DirectionGuard {
+            previous: DIRECTION.with(|current| current.replace(Some(direction))),
+        }
+    }
+}
+
+impl Drop for DirectionGuard {
+    fn drop(&mut self) {
+        DIRECTION.with(|current| current.set(self.previous));
+    }
+}
+
+/// Calls `f` with the base URLs of the schema resources entered so far by the validation
+/// running on this thread, outermost first, which are only tracked when they lead to dynamic
+/// references, see `Scope::tracks_dynamic_scope`. Used to resolve `$dynamicRef` and
+/// `$recursiveRef`.
+pub fn with_dynamic_scope<F, T>(f: F) -> T
+where
+    F: FnOnce(&[url::Url]) -> T,
+{
+    DYNAMIC_SCOPE.with(|scope| f(&scope.borrow()))
+}
+
 pub struct ScopedSchema<'scope, 'schema: 'scope, V>
 where
     V: Value,
@@ -40,6 +101,10 @@ where
 {
     pub keywords: keywords::KeywordMap<V>,
     pub ban_unknown_keywords: bool,
+    pub draft: Draft,
+    pub format_mode: FormatMode,
+    pub regex_engine: Arc<dyn regex_engine::RegexEngine>,
+    pub regex_limits: regex_engine::RegexLimits,
 }
 
 impl<V> CompilationSettings<V>
@@ -53,6 +118,129 @@ where
         CompilationSettings {
             keywords,
             ban_unknown_keywords,
+            draft: Draft::Draft7,
+            format_mode: FormatMode::Assert,
+            regex_engine: Arc::new(regex_engine::EcmaEngine),
+            regex_limits: regex_engine::RegexLimits::default(),
+        }
+    }
+
+    /// Selects the draft whose identifier keyword (`id` or `$id`) the schema uses, and its default
+    /// format mode. The keywords should be the matching map, e.g. `keywords::draft4()` for
+    /// `Draft::Draft4`.
+    pub fn with_draft(mut self, draft: Draft) -> CompilationSettings<V> {
+        self.draft = draft;
+        self.format_mode = draft.format_mode();
+        self
+    }
+
+    pub fn with_format_mode(mut self, format_mode: FormatMode) -> CompilationSettings<V> {
+        self.format_mode = format_mode;
+        self
+    }
+
+    pub fn with_regex_engine(
+        mut self,
+        regex_engine: Arc<dyn regex_engine::RegexEngine>,
+    ) -> CompilationSettings<V> {
+        self.regex_engine = regex_engine;
+        self
+    }
+
+    pub fn with_regex_limits(
+        mut self,
+        regex_limits: regex_engine::RegexLimits,
+    ) -> CompilationSettings<V> {
+        self.regex_limits = regex_limits;
+        self
+    }
+}
+
+/// How the `format` keyword treats values that don't match their format.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormatMode {
+    /// Failures are errors, the default up to draft-07.
+    Assert,
+    /// Failures are only reported as warnings, the default since draft 2019-09.
+    Annotate,
+    /// Failures of the listed formats are errors, the others are warnings.
+    AssertOnly(hashbrown::HashSet<String>),
+}
+
+impl FormatMode {
+    pub fn asserts(&self, format: &str) -> bool {
+        match self {
+            FormatMode::Assert => true,
+            FormatMode::Annotate => false,
+            FormatMode::AssertOnly(formats) => formats.contains(format),
+        }
+    }
+}
+
+/// What `ScopedSchema::validate_normalized` changes in an instance before validating it.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NormalizeOptions {
+    /// Inserts the `default` of missing properties.
+    pub defaults: bool,
+    /// Converts values to match `type` where that is lossless, like ajv's `coerceTypes`: numeric
+    /// strings to numbers, `"true"`/`"false"` to booleans, scalars to single-item arrays, and
+    /// `null` to `""`, `0` or `false`.
+    pub coerce_types: bool,
+    /// Which properties not declared by `properties` or `patternProperties` are removed.
+    pub remove_additional: RemoveAdditional,
+    /// Removes the properties `readOnly` (for requests) or `writeOnly` (for responses) in their
+    /// subschema under `properties`, and validates the result in that direction.
+    pub strip: Option<Direction>,
+}
+
+/// Removal of undeclared properties, by `NormalizeOptions::remove_additional`. Each schema with
+/// `properties`, `patternProperties` or `additionalProperties` only knows its own declarations,
+/// so siblings under `allOf` should declare the same properties.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RemoveAdditional {
+    #[default]
+    Never,
+    /// Where `additionalProperties` is `false`.
+    Forbidden,
+    /// Wherever the schema has `properties`, `patternProperties` or `additionalProperties`.
+    All,
+}
+
+/// JSON Schema drafts. A dialect builds on one of them, which decides how schemas are identified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Draft {
+    Draft4,
+    Draft6,
+    Draft7,
+    Draft2019_09,
+    Draft2020_12,
+}
+
+impl Draft {
+    /// The URI of the draft's metaschema, without the empty fragment.
+    pub fn metaschema(self) -> &'static str {
+        match self {
+            Draft::Draft4 => "http://json-schema.org/draft-04/schema",
+            Draft::Draft6 => "http://json-schema.org/draft-06/schema",
+            Draft::Draft7 => "http://json-schema.org/draft-07/schema",
+            Draft::Draft2019_09 => "https://json-schema.org/draft/2019-09/schema",
+            Draft::Draft2020_12 => "https://json-schema.org/draft/2020-12/schema",
+        }
+    }
+
+    /// How `format` treats failures unless set otherwise: draft 2019-09 made it an annotation.
+    pub fn format_mode(self) -> FormatMode {
+        match self {
+            Draft::Draft4 | Draft::Draft6 | Draft::Draft7 => FormatMode::Assert,
+            Draft::Draft2019_09 | Draft::Draft2020_12 => FormatMode::Annotate,
+        }
+    }
+
+    /// The keyword holding the URI of a schema.
+    pub fn id_key(self) -> &'static str {
+        match self {
+            Draft::Draft4 => "id",
+            _ => "$id",
         }
     }
 }
@@ -64,14 +252,76 @@ pub enum SchemaError {
     NotAnObject,
     UrlParseError(url::ParseError),
     UnknownKey(String),
-    Malformed { path: String, detail: String },
+    UnknownDialect(String),
+    UnknownVocabulary(String),
+    Malformed {
+        path: String,
+        detail: String,
+    },
+    /// A pattern compiles to more than `RegexLimits::size_limit` bytes.
+    RegexTooLarge {
+        path: String,
+        limit: usize,
+    },
+    /// A `$ref` points at a schema that isn't in the scope and that its resolver doesn't know,
+    /// see `Scope::ban_unresolved_refs`.
+    UnresolvedRef(String),
+    /// A schema reaches itself through `$ref`s and other subschemas applied to the same value,
+    /// e.g. `{"$ref": "#"}`, so validating against it would never end. Holds the URL of the
+    /// subschema closing the cycle.
+    RefCycle(String),
+    /// A pattern has more than `RegexLimits::pattern_length` characters.
+    PatternTooLong {
+        path: String,
+        limit: usize,
+    },
+    /// The schema doesn't validate against its metaschema, see `Scope::validate_schemas`. The
+    /// paths of the errors point into the schema.
+    Invalid(super::error::SimdjsonSchemaErrors),
+}
+
+/// The URL of a subschema a validator applies. `Scope` links it to the schema once that is in
+/// the scope, so validation follows an index instead of looking the URL up, see `Scope::follow`.
+#[derive(Debug, Clone)]
+pub struct Link {
+    url: url::Url,
+    target: Arc<sync::OnceLock<usize>>,
+}
+
+impl Link {
+    pub fn new(url: url::Url) -> Link {
+        Link {
+            url,
+            target: Arc::new(sync::OnceLock::new()),
+        }
+    }
+
+    pub fn url(&self) -> &url::Url {
+        &self.url
+    }
+
+    /// The index of the linked schema in the scope, if the URL has been linked.
+    pub fn target(&self) -> Option<usize> {
+        self.target.get().copied()
+    }
+
+    pub(crate) fn link(&self, target: usize) {
+        let _ = self.target.set(target);
+    }
 }
 
 #[derive(Debug)]
 pub struct WalkContext<'walk> {
     pub url: &'walk url::Url,
     pub fragment: Vec<String>,
+    /// Path from the compiled root to the schema resource `url` identifies; `fragment` is
+    /// relative to it.
+    pub resource: Vec<String>,
     pub scopes: &'walk mut hashbrown::HashMap<String, Vec<String>>,
+    pub format_mode: &'walk FormatMode,
+    pub regex_engine: &'walk dyn regex_engine::RegexEngine,
+    pub regex_limits: &'walk regex_engine::RegexLimits,
+    pub links: &'walk cell::RefCell<Vec<Link>>,
 }
 
 impl<'walk> WalkContext<'walk> {
@@ -84,6 +334,33 @@ impl<'walk> WalkContext<'walk> {
                 .as_ref(),
         )
     }
+
+    /// Makes the link of a subschema URL, which `Scope` binds once the schema is compiled.
+    pub fn link(&self, url: url::Url) -> Link {
+        let link = Link::new(url);
+        self.links.borrow_mut().push(link.clone());
+        link
+    }
+
+    pub fn tree_path(&self) -> Vec<String> {
+        let mut path = self.resource.clone();
+        path.extend(self.fragment.iter().cloned());
+        path
+    }
+
+    /// Compiles a pattern of the schema with the configured engine, within the configured limits.
+    pub fn compile_regex(
+        &self,
+        pattern: &str,
+    ) -> Result<Box<dyn regex_engine::Regex>, regex_engine::RegexError> {
+        if let Some(limit) = self.regex_limits.pattern_length {
+            if pattern.chars().count() > limit {
+                return Err(regex_engine::RegexError::TooLong(limit));
+            }
+        }
+
+        self.regex_engine.compile(pattern, self.regex_limits)
+    }
 }
 
 impl<'scope, 'schema, V> ScopedSchema<'scope, 'schema, V>
@@ -94,10 +371,15 @@ where
         scope: &'scope scope::Scope<V>,
         schema: &'schema Schema<V>,
     ) -> ScopedSchema<'scope, 'schema, V> {
-        ScopedSchema {
-            scope,
-            schema: &schema,
-        }
+        ScopedSchema { scope, schema }
+    }
+
+    /// The metaschema the `$schema` of the schema names, see `Schema::metaschema`.
+    pub fn metaschema(&self) -> Option<&url::Url>
+    where
+        <V as Value>::Key: std::borrow::Borrow<str> + std::convert::AsRef<str> + std::fmt::Debug,
+    {
+        self.schema.metaschema()
     }
 
     pub fn validate(&self, data: &V) -> validators::ValidationState
@@ -105,15 +387,96 @@ where
         V: Value + std::fmt::Debug,
         <V as Value>::Key: std::borrow::Borrow<str> + std::convert::AsRef<str> + std::fmt::Debug,
     {
-        dbg!(data.clone());
-        self.schema.validate_in_scope(data, "", &self.scope)
+        self.schema.validate_in_scope(data, "", self.scope)
+    }
+
+    /// Validates `data` as sent in `direction`, so `readOnly` values fail requests and
+    /// `writeOnly` values fail responses.
+    pub fn validate_for(&self, data: &V, direction: Direction) -> validators::ValidationState
+    where
+        V: Value + std::fmt::Debug,
+        <V as Value>::Key: std::borrow::Borrow<str> + std::convert::AsRef<str> + std::fmt::Debug,
+    {
+        let _direction = DirectionGuard::enter(direction);
+        self.validate(data)
     }
 
     pub fn validate_in(&self, data: &V, path: &str) -> validators::ValidationState
     where
         <V as Value>::Key: std::borrow::Borrow<str> + std::convert::AsRef<str> + std::fmt::Debug,
     {
-        self.schema.validate_in_scope(data, path, &self.scope)
+        self.schema.validate_in_scope(data, path, self.scope)
+    }
+
+    pub fn source(&self) -> &OwnedValue {
+        &self.schema.source
+    }
+
+    /// Fills the missing properties of `data` whose subschema under `properties` has a `default`,
+    /// following subschemas of present values, items and references, then validates the result.
+    /// Returns the validation state and the JSON pointers of the inserted values.
+    pub fn validate_with_defaults(&self, data: &mut V) -> (validators::ValidationState, Vec<String>)
+    where
+        V: Mutable + std::fmt::Debug,
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::convert::From<String>
+            + std::fmt::Debug,
+    {
+        let options = NormalizeOptions {
+            defaults: true,
+            ..NormalizeOptions::default()
+        };
+        let (state, normalization) = self.validate_normalized(data, options);
+
+        (state, normalization.populated)
+    }
+
+    /// Rewrites `data` as `options` ask, then validates the result, in the direction of
+    /// `NormalizeOptions::strip` if it is set.
+    pub fn validate_normalized(
+        &self,
+        data: &mut V,
+        options: NormalizeOptions,
+    ) -> (validators::ValidationState, validators::Normalization)
+    where
+        V: Mutable + std::fmt::Debug,
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::convert::From<String>
+            + std::fmt::Debug,
+    {
+        let mut normalization = validators::Normalization::new(options);
+        self.normalize_in(data, "", &mut normalization);
+
+        let state = match options.strip {
+            Some(direction) => self.validate_for(data, direction),
+            None => self.validate(data),
+        };
+
+        (state, normalization)
+    }
+
+    pub fn normalize_in(
+        &self,
+        data: &mut V,
+        path: &str,
+        normalization: &mut validators::Normalization,
+    ) where
+        V: Mutable,
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::convert::From<String>
+            + std::fmt::Debug,
+    {
+        self.schema
+            .normalize_in_scope(data, path, self.scope, normalization)
     }
 }
 
@@ -130,19 +493,131 @@ where
     ) -> validators::ValidationState {
         let mut state = validators::ValidationState::new();
 
-        for validator in self.validators.iter() {
-            println!("VALIDATOR");
+        let depth = DEPTH.with(|depth| depth.get());
+        if depth >= scope.depth_limit() {
+            state.errors.push(Box::new(error::MaxDepth {
+                path: path.to_string(),
+                detail: format!(
+                    "The validation is nested in more than {} schemas",
+                    scope.depth_limit()
+                ),
+            }));
+            return state;
+        }
+        DEPTH.with(|current| current.set(depth + 1));
+
+        let resource = self.id.as_ref().filter(|id| scope.tracks_dynamic_scope(id));
+        if let Some(id) = resource {
+            DYNAMIC_SCOPE.with(|scope| scope.borrow_mut().push(id.clone()));
+        }
+
+        for validator in self.validators.iter().filter(|v| !v.is_deferred()) {
             state.append(validator.validate(data, path, scope))
         }
 
+        for validator in self.validators.iter().filter(|v| v.is_deferred()) {
+            let deferred_state = validator.validate_evaluated(data, path, scope, &state);
+            state.append(deferred_state)
+        }
+
+        if resource.is_some() {
+            DYNAMIC_SCOPE.with(|scope| scope.borrow_mut().pop());
+        }
+        DEPTH.with(|current| current.set(depth));
+
         state
     }
+
+    fn normalize_in_scope(
+        &self,
+        data: &mut V,
+        path: &str,
+        scope: &scope::Scope<V>,
+        normalization: &mut validators::Normalization,
+    ) where
+        V: Mutable,
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::convert::From<String>,
+    {
+        // The validation that follows reports values nested too deep
+        let depth = DEPTH.with(|depth| depth.get());
+        if depth >= scope.depth_limit() {
+            return;
+        }
+        DEPTH.with(|current| current.set(depth + 1));
+
+        let resource = self.id.as_ref().filter(|id| scope.tracks_dynamic_scope(id));
+        if let Some(id) = resource {
+            DYNAMIC_SCOPE.with(|scope| scope.borrow_mut().push(id.clone()));
+        }
+
+        // The value gets its final type before its subschemas see it
+        if normalization.options.coerce_types {
+            for validator in self.validators.iter() {
+                validator.coerce(data, path, &mut normalization.coerced)
+            }
+        }
+
+        for validator in self.validators.iter() {
+            validator.normalize(data, path, scope, normalization)
+        }
+
+        if resource.is_some() {
+            DYNAMIC_SCOPE.with(|scope| scope.borrow_mut().pop());
+        }
+        DEPTH.with(|current| current.set(depth));
+    }
+
+    /// The schemas the validators of this schema and its subschemas apply outside of them, e.g.
+    /// `$ref` targets.
+    pub fn references(&self) -> Vec<&url::Url> {
+        let mut references = self.own_references();
+        for schema in self.tree.values() {
+            references.extend(schema.references());
+        }
+
+        references
+    }
+
+    /// The schemas the validators of this schema apply outside of it, without the ones of its
+    /// subschemas.
+    pub fn own_references(&self) -> Vec<&url::Url> {
+        self.validators
+            .iter()
+            .flat_map(|validator| validator.references())
+            .collect()
+    }
+
+    /// The keys of the values on the path from this schema to its subschema with the id or
+    /// anchor `id`, see `resolve`.
+    pub fn resolve_path(&self, id: &str) -> Option<&[String]> {
+        self.scopes.get(id).map(|path| path.as_slice())
+    }
+
     pub fn resolve(&self, id: &str) -> Option<&Schema<V>> {
         let path = self.scopes.get(id);
         path.map(|path| {
             let mut schema = self;
             for item in path.iter() {
-                schema = &schema.tree[item]
+                schema = &*schema.tree[&helpers::encode(item)]
+            }
+            schema
+        })
+    }
+
+    /// Like `resolve`, for a schema shared by the links of a scope.
+    pub(crate) fn resolve_shared<'schema>(
+        self: &'schema Arc<Self>,
+        id: &str,
+    ) -> Option<&'schema Arc<Schema<V>>> {
+        let path = self.scopes.get(id);
+        path.map(|path| {
+            let mut schema = self;
+            for item in path.iter() {
+                schema = &schema.tree[&helpers::encode(item)]
             }
             schema
         })
@@ -154,7 +629,26 @@ where
         let parts = fragment[1..].split('/');
         let mut schema = self;
         for part in parts {
-            match schema.tree.get(part) {
+            match schema.tree.get(&helpers::encode(&helpers::decode(part))) {
+                Some(sch) => schema = sch.as_ref(),
+                None => return None,
+            }
+        }
+
+        Some(schema)
+    }
+
+    /// Like `resolve_fragment`, for a schema shared by the links of a scope.
+    pub(crate) fn resolve_fragment_shared<'schema>(
+        self: &'schema Arc<Self>,
+        fragment: &str,
+    ) -> Option<&'schema Arc<Schema<V>>> {
+        assert!(fragment.starts_with('/'), "Can't resolve id fragments");
+
+        let parts = fragment[1..].split('/');
+        let mut schema = self;
+        for part in parts {
+            match schema.tree.get(&helpers::encode(&helpers::decode(part))) {
                 Some(sch) => schema = sch,
                 None => return None,
             }
@@ -163,6 +657,73 @@ where
         Some(schema)
     }
 
+    /// Whether a validator of this schema or its subschemas resolves its subschema dynamically,
+    /// see `Validator::is_dynamic`.
+    pub fn has_dynamic_refs(&self) -> bool {
+        self.validators
+            .iter()
+            .any(|validator| validator.is_dynamic())
+            || self.tree.values().any(|schema| schema.has_dynamic_refs())
+    }
+
+    /// Whether a validator of this schema or its subschemas looks at what the others evaluated,
+    /// see `Validator::is_deferred`.
+    pub fn has_deferred(&self) -> bool {
+        self.validators
+            .iter()
+            .any(|validator| validator.is_deferred())
+            || self.tree.values().any(|schema| schema.has_deferred())
+    }
+
+    /// The ids of this schema and its subschemas that are schema resources, i.e. have an id.
+    pub fn resource_ids(&self) -> Vec<&url::Url> {
+        self.id
+            .iter()
+            .chain(self.tree.values().flat_map(|schema| schema.resource_ids()))
+            .collect()
+    }
+
+    /// The links of the validators of this schema to the schemas they apply to the instance
+    /// itself, see `Validator::in_place`.
+    pub fn in_place(&self) -> Vec<&Link> {
+        self.validators
+            .iter()
+            .flat_map(|validator| validator.in_place())
+            .collect()
+    }
+
+    /// The metaschema the `$schema` of this schema names, which picks its dialect.
+    pub fn metaschema(&self) -> Option<&url::Url> {
+        self.schema.as_ref()
+    }
+
+    /// The JSON the schema was compiled from.
+    pub fn source(&self) -> &OwnedValue {
+        &self.source
+    }
+
+    /// The subschema compiled from the value of `key` in the source of this schema, which is an
+    /// array index for an array.
+    pub fn child(&self, key: &str) -> Option<&Schema<V>> {
+        self.tree
+            .get(&helpers::encode(key))
+            .map(|schema| schema.as_ref())
+    }
+
+    /// The direct subschemas of this schema, by the key of their value in its source escaped as
+    /// in the fragment of a URL.
+    pub fn subschemas(&self) -> impl Iterator<Item = (&str, &Schema<V>)> {
+        self.tree
+            .iter()
+            .map(|(key, schema)| (key.as_str(), schema.as_ref()))
+    }
+
+    /// The links of the subschema URLs of the validators of this schema and its subschemas, if
+    /// this schema was compiled as a document.
+    pub fn links(&self) -> &[Link] {
+        &self.links
+    }
+
     fn compile(
         source: OwnedValue,
         external_id: Option<url::Url>,
@@ -176,30 +737,30 @@ where
             + std::fmt::Debug,
     {
         let source = helpers::convert_boolean_schema(source);
-        dbg!(source.clone());
 
         if !source.is_object() {
             return Err(SchemaError::NotAnObject);
         }
 
-        let id = if external_id.is_some() {
-            external_id.unwrap()
-        } else {
-            helpers::parse_url_key("$id", &source)?
+        let id = match external_id {
+            Some(id) => id,
+            None => helpers::parse_url_key(settings.draft.id_key(), &source)?
                 .clone()
-                .unwrap_or_else(helpers::generate_id)
+                .unwrap_or_else(helpers::generate_id),
         };
 
         let schema = helpers::parse_url_key("$schema", &source)?;
 
+        let links = cell::RefCell::new(vec![]);
+
         let (tree, mut scopes) = {
             let mut tree = collections::BTreeMap::new();
             let obj = source.as_object().unwrap();
 
             let mut scopes = hashbrown::HashMap::new();
+            Schema::<V>::register_anchors(&source, &id, &[], &mut scopes);
 
             for (key, val) in obj.iter() {
-                dbg!(key);
                 if !val.is_object() && !val.is_array() && !val.is_bool() {
                     continue;
                 }
@@ -212,7 +773,12 @@ where
                     url: &id,
                     // NOTE: ToString bound stems from here
                     fragment: vec![key.to_string().clone()],
+                    resource: vec![],
                     scopes: &mut scopes,
+                    format_mode: &settings.format_mode,
+                    regex_engine: settings.regex_engine.as_ref(),
+                    regex_limits: &settings.regex_limits,
+                    links: &links,
                 };
 
                 let scheme = Schema::compile_sub(
@@ -222,7 +788,7 @@ where
                     !NON_SCHEMA_KEYS.contains(key.as_str()),
                 )?;
 
-                tree.insert(helpers::encode(key.as_ref()), scheme);
+                tree.insert(helpers::encode(key.as_ref()), Arc::new(scheme));
             }
 
             (tree, scopes)
@@ -233,7 +799,12 @@ where
             &WalkContext {
                 url: &id,
                 fragment: vec![],
+                resource: vec![],
                 scopes: &mut scopes,
+                format_mode: &settings.format_mode,
+                regex_engine: settings.regex_engine.as_ref(),
+                regex_limits: &settings.regex_limits,
+                links: &links,
             },
             &settings,
         )?;
@@ -245,6 +816,7 @@ where
             tree,
             validators,
             scopes,
+            links: links.into_inner(),
         };
 
         Ok(schema)
@@ -272,40 +844,32 @@ where
             .map(|key| key.to_string())
             .collect();
         let mut not_consumed = hashbrown::HashSet::new();
-        dbg!(source.clone());
-        dbg!(keys.clone());
-
-        loop {
-            let key = keys.iter().next().cloned();
-            if key.is_some() {
-                let key = key.unwrap();
-                match settings.keywords.get(&key) {
-                    Some(keyword) => {
-                        keyword.consume(&mut keys);
-
-                        let is_exclusive_keyword = keyword.keyword.is_exclusive();
-
-                        if let Some(validator) = keyword.keyword.compile(&source, context)? {
-                            if is_exclusive_keyword {
-                                validators = vec![validator];
-                            } else {
-                                validators.push(validator);
-                            }
-                        }
 
+        while let Some(key) = keys.iter().next().cloned() {
+            match settings.keywords.get(&key) {
+                Some(keyword) => {
+                    keyword.consume(&mut keys);
+
+                    let is_exclusive_keyword = keyword.keyword.is_exclusive();
+
+                    if let Some(validator) = keyword.keyword.compile(&source, context)? {
                         if is_exclusive_keyword {
-                            break;
+                            validators = vec![validator];
+                        } else {
+                            validators.push(validator);
                         }
                     }
-                    None => {
-                        keys.remove(&key);
-                        if settings.ban_unknown_keywords {
-                            not_consumed.insert(key);
-                        }
+
+                    if is_exclusive_keyword {
+                        break;
+                    }
+                }
+                None => {
+                    keys.remove(&key);
+                    if settings.ban_unknown_keywords {
+                        not_consumed.insert(key);
                     }
                 }
-            } else {
-                break;
             }
         }
 
@@ -320,6 +884,24 @@ where
         Ok(validators)
     }
 
+    /// Registers the `$anchor` and `$dynamicAnchor` of a schema as plain-name fragments of its
+    /// base URL, so `base#name` resolves to the schema at `path`.
+    fn register_anchors(
+        source: &OwnedValue,
+        base: &url::Url,
+        path: &[String],
+        scopes: &mut hashbrown::HashMap<String, Vec<String>>,
+    ) {
+        for key in ["$anchor", "$dynamicAnchor"].iter() {
+            if let Some(anchor) = source.get(*key).and_then(|anchor| anchor.as_str()) {
+                scopes.insert(
+                    helpers::anchor_url(base, anchor).into_string(),
+                    path.to_vec(),
+                );
+            }
+        }
+    }
+
     fn compile_sub(
         source: OwnedValue,
         context: &mut WalkContext<'_>,
@@ -333,10 +915,22 @@ where
     {
         let source = helpers::convert_boolean_schema(source);
 
-        let id = if is_schema {
-            helpers::parse_url_key_with_base("$id", &source, context.url)?
+        let (id, id_anchor) = if is_schema {
+            match helpers::parse_url_key_with_base(settings.draft.id_key(), &source, context.url)? {
+                Some(id) => helpers::split_id(id),
+                None => (None, None),
+            }
+        } else {
+            (None, None)
+        };
+
+        let tree_path = context.tree_path();
+
+        // Subschemas of a new schema resource are addressed relative to its `$id`
+        let (base_fragment, resource) = if id.is_some() {
+            (vec![], tree_path.clone())
         } else {
-            None
+            (context.fragment.clone(), context.resource.clone())
         };
 
         let schema = if is_schema {
@@ -361,7 +955,7 @@ where
                         continue;
                     }
 
-                    let mut current_fragment = context.fragment.clone();
+                    let mut current_fragment = base_fragment.clone();
                     current_fragment.push(key.to_string().clone());
 
                     let is_schema = PROPERTY_KEYS.contains(&parent_key[..])
@@ -370,13 +964,18 @@ where
                     let mut context = WalkContext {
                         url: id.as_ref().unwrap_or(context.url),
                         fragment: current_fragment,
+                        resource: resource.clone(),
                         scopes: context.scopes,
+                        format_mode: context.format_mode,
+                        regex_engine: context.regex_engine,
+                        regex_limits: context.regex_limits,
+                        links: context.links,
                     };
 
                     let scheme =
-                        Schema::compile_sub(val.clone(), &mut context, &settings, is_schema)?;
+                        Schema::compile_sub(val.clone(), &mut context, settings, is_schema)?;
 
-                    tree.insert(helpers::encode(key.as_ref()), scheme);
+                    tree.insert(helpers::encode(key.as_ref()), Arc::new(scheme));
                 }
             } else if source.is_array() {
                 let array = source.as_array().unwrap();
@@ -393,42 +992,77 @@ where
                         continue;
                     }
 
-                    let mut current_fragment = context.fragment.clone();
+                    let mut current_fragment = base_fragment.clone();
                     current_fragment.push(idx.to_string().clone());
 
                     let mut context = WalkContext {
                         url: id.as_ref().unwrap_or(context.url),
                         fragment: current_fragment,
+                        resource: resource.clone(),
                         scopes: context.scopes,
+                        format_mode: context.format_mode,
+                        regex_engine: context.regex_engine,
+                        regex_limits: context.regex_limits,
+                        links: context.links,
                     };
 
                     let scheme = Schema::compile_sub(val.clone(), &mut context, settings, true)?;
 
-                    tree.insert(idx.to_string().clone(), scheme);
+                    tree.insert(idx.to_string().clone(), Arc::new(scheme));
                 }
             }
             tree
         };
 
-        if id.is_some() {
+        if let Some(ref id) = id {
+            context
+                .scopes
+                .insert(id.clone().into_string(), tree_path.clone());
+        }
+
+        if let Some(id_anchor) = id_anchor {
             context
                 .scopes
-                .insert(id.clone().unwrap().into_string(), context.fragment.clone());
+                .insert(id_anchor.into_string(), tree_path.clone());
+        }
+
+        if is_schema {
+            Schema::<V>::register_anchors(
+                &source,
+                id.as_ref().unwrap_or(context.url),
+                &tree_path,
+                context.scopes,
+            );
         }
 
         let validators = if is_schema && source.is_object() {
-            Schema::compile_keywords(source.clone(), context, settings)?
+            if let Some(ref url) = id {
+                let context = WalkContext {
+                    url,
+                    fragment: vec![],
+                    resource: tree_path,
+                    scopes: context.scopes,
+                    format_mode: context.format_mode,
+                    regex_engine: context.regex_engine,
+                    regex_limits: context.regex_limits,
+                    links: context.links,
+                };
+                Schema::compile_keywords(source.clone(), &context, settings)?
+            } else {
+                Schema::compile_keywords(source.clone(), context, settings)?
+            }
         } else {
             vec![]
         };
 
         let schema = Schema {
-            id: id,
+            id,
             schema,
             source,
             tree,
             validators,
             scopes: hashbrown::HashMap::new(),
+            links: vec![],
         };
 
         Ok(schema)
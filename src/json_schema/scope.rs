@@ -1,24 +1,114 @@
 use super::helpers;
 use super::keywords;
+use super::loader;
+use super::metaschemas;
+use super::regex_engine;
+use super::resolver;
 use super::schema;
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 use simd_json::value::owned::Value as OwnedValue;
+use std::path;
+use std::sync::Arc;
 use value_trait::*;
 
+/// How many schemas a validation nests in by default, see `Scope::max_depth`.
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+/// The keywords a metaschema URI in `$schema` stands for.
+#[derive(Debug)]
+pub struct Dialect<V>
+where
+    V: Value,
+{
+    pub draft: schema::Draft,
+    pub keywords: keywords::KeywordMap<V>,
+}
+
+impl<V> Clone for Dialect<V>
+where
+    V: Value,
+{
+    fn clone(&self) -> Self {
+        Dialect {
+            draft: self.draft,
+            keywords: self.keywords.clone(),
+        }
+    }
+}
+
+/// A schema with its `$ref`s replaced by the schemas they point at, see `Scope::dereference`.
+#[derive(Debug)]
+pub struct Dereferenced {
+    pub schema: OwnedValue,
+    /// JSON pointers into `schema` of the `$ref`s left in place because they point at a schema
+    /// they are in.
+    pub recursive: Vec<String>,
+}
+
+// The state of `Scope::dereference` walking a schema
+struct Dereferencing {
+    draft: schema::Draft,
+    // The base URL of the dereferenced schema
+    base: url::Url,
+    // Locations of the schemas being walked, outermost first, see `Scope::schema_location`
+    schemas: Vec<String>,
+    // Tokens of the JSON pointer to the walked value in the result
+    path: Vec<String>,
+    recursive: Vec<String>,
+}
+
 #[derive(Debug)]
 pub struct Scope<V>
 where
     V: Value,
 {
-    keywords: keywords::KeywordMap<V>,
-    schemes: HashMap<String, schema::Schema<V>>,
+    dialects: HashMap<String, Dialect<V>>,
+    vocabularies: HashMap<String, keywords::KeywordMap<V>>,
+    ban_unknown_dialects: bool,
+    validate_schemas: bool,
+    metaschemas_loaded: bool,
+    format_mode: Option<schema::FormatMode>,
+    regex_engine: Arc<dyn regex_engine::RegexEngine>,
+    regex_limits: regex_engine::RegexLimits,
+    resolver: Option<Arc<dyn resolver::Resolver>>,
+    ban_unresolved_refs: bool,
+    max_depth: usize,
+    schemes: HashMap<String, Arc<schema::Schema<V>>>,
+    // The schemas links point at, by their index, and the index of each linked URL
+    targets: Vec<Arc<schema::Schema<V>>>,
+    target_indexes: HashMap<String, usize>,
+    // The ids of the schema resources validations track for `$dynamicRef` and `$recursiveRef`
+    dynamic_resources: HashSet<String>,
+    unevaluated: bool,
+}
+
+impl<V> Default for Scope<V>
+where
+    V: Value
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::clone::Clone
+        + std::marker::Sync
+        + std::marker::Send
+        + std::cmp::PartialEq
+        + std::fmt::Display
+        + 'static,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::convert::AsRef<str>
+        + std::fmt::Display
+        + std::marker::Sync
+        + std::marker::Send
+        + std::fmt::Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<V> Scope<V>
 where
     V: Value,
 {
-    pub fn new<'scope>() -> Scope<V>
+    pub fn new() -> Scope<V>
     where
         V: Value
             + std::convert::From<simd_json::value::owned::Value>
@@ -26,7 +116,8 @@ where
             + std::marker::Sync
             + std::marker::Send
             + std::cmp::PartialEq
-            + std::fmt::Display,
+            + std::fmt::Display
+            + 'static,
         <V as Value>::Key: std::borrow::Borrow<str>
             + std::convert::AsRef<str>
             + std::fmt::Display
@@ -34,17 +125,289 @@ where
             + std::marker::Send
             + std::fmt::Debug,
     {
-        let scope = Scope {
-            keywords: keywords::default(),
+        let mut scope = Scope {
+            dialects: HashMap::new(),
+            vocabularies: HashMap::new(),
+            ban_unknown_dialects: false,
+            validate_schemas: false,
+            metaschemas_loaded: false,
+            format_mode: None,
+            regex_engine: Arc::new(regex_engine::EcmaEngine),
+            regex_limits: regex_engine::RegexLimits::default(),
+            resolver: None,
+            ban_unresolved_refs: false,
+            max_depth: DEFAULT_MAX_DEPTH,
             schemes: HashMap::new(),
+            targets: vec![],
+            target_indexes: HashMap::new(),
+            dynamic_resources: HashSet::new(),
+            unevaluated: false,
         };
 
-        //scope.add_keyword(vec!["format".to_string()], keywords::format::Format::new());
+        let drafts = vec![
+            (schema::Draft::Draft4, keywords::draft4()),
+            (schema::Draft::Draft6, keywords::draft6()),
+            (schema::Draft::Draft7, keywords::default()),
+            (schema::Draft::Draft2019_09, keywords::draft2019_09()),
+            (schema::Draft::Draft2020_12, keywords::draft2020_12()),
+        ];
+
+        for (draft, keywords) in drafts {
+            let metaschema = draft.metaschema();
+            scope.dialects.insert(
+                other_scheme(metaschema),
+                Dialect {
+                    draft,
+                    keywords: keywords.clone(),
+                },
+            );
+            scope
+                .dialects
+                .insert(metaschema.to_string(), Dialect { draft, keywords });
+        }
+
+        let vocabularies = keywords::vocabularies2019_09()
+            .into_iter()
+            .chain(keywords::vocabularies2020_12());
+        for (vocabulary, keywords) in vocabularies {
+            scope.vocabularies.insert(vocabulary.to_string(), keywords);
+        }
 
         scope
     }
 
-    pub fn resolve(&self, id: &url::Url) -> Option<schema::ScopedSchema<V>>
+    /// Registers the keywords of a vocabulary. A custom metaschema compiled into this scope selects
+    /// the keywords of the schemas using it by listing vocabularies in its `$vocabulary`.
+    pub fn add_vocabulary(&mut self, vocabulary: &url::Url, keywords: keywords::KeywordMap<V>) {
+        self.vocabularies
+            .insert(vocabulary.as_str().to_string(), keywords);
+    }
+
+    /// Registers the keywords of a custom metaschema, e.g. a draft's keyword map extended with
+    /// `keywords::decouple_keyword`. `add_keyword` can add to it later.
+    pub fn add_dialect(
+        &mut self,
+        metaschema: &url::Url,
+        draft: schema::Draft,
+        keywords: keywords::KeywordMap<V>,
+    ) {
+        let (key, _) = helpers::serialize_schema_path(metaschema);
+        self.dialects.insert(key, Dialect { draft, keywords });
+    }
+
+    /// Makes compilation fail with `SchemaError::UnknownDialect` for a `$schema` without a
+    /// registered dialect, instead of falling back to draft-07.
+    pub fn ban_unknown_dialects(&mut self, ban: bool) {
+        self.ban_unknown_dialects = ban;
+    }
+
+    /// Makes compilation fail with `SchemaError::Invalid` for a schema that doesn't validate
+    /// against the metaschema named by its `$schema`, or the one of its dialect's draft if that
+    /// metaschema isn't in this scope. The metaschemas of the drafts are bundled.
+    pub fn validate_schemas(&mut self, validate: bool) {
+        self.validate_schemas = validate;
+    }
+
+    /// Sets whether `format` failures of the schemas compiled from now on are errors or warnings,
+    /// instead of the default of their dialect's draft, see `Draft::format_mode`. Metaschemas
+    /// listing the format-assertion vocabulary always assert.
+    pub fn format_mode(&mut self, format_mode: schema::FormatMode) {
+        self.format_mode = Some(format_mode);
+    }
+
+    /// Sets the engine compiling `pattern` and `patternProperties` of the schemas compiled from now
+    /// on, `regex_engine::EcmaEngine` by default.
+    pub fn regex_engine<E>(&mut self, regex_engine: E)
+    where
+        E: regex_engine::RegexEngine + 'static,
+    {
+        self.regex_engine = Arc::new(regex_engine);
+    }
+
+    /// Bounds the patterns of the schemas compiled from now on and the strings they are tested
+    /// against.
+    pub fn regex_limits(&mut self, regex_limits: regex_engine::RegexLimits) {
+        self.regex_limits = regex_limits;
+    }
+
+    /// Sets where the schemas compiled from now on get the schemas their `$ref`s point at that
+    /// aren't in this scope yet. Those are compiled into this scope along with them.
+    pub fn resolver<R>(&mut self, resolver: R)
+    where
+        R: resolver::Resolver + 'static,
+    {
+        self.resolver = Some(Arc::new(resolver));
+    }
+
+    /// Makes compilation fail with `SchemaError::UnresolvedRef` for a `$ref` that points at a
+    /// schema neither in this scope nor known to its resolver, instead of the `$ref` being
+    /// reported in `ValidationState::missing` by every validation.
+    pub fn ban_unresolved_refs(&mut self, ban: bool) {
+        self.ban_unresolved_refs = ban;
+    }
+
+    /// Bounds how many schemas a validation nests in, counting each `$ref` and each subschema
+    /// applied to the value or a value in it. Beyond that, the value is reported as an
+    /// `error::MaxDepth` instead of its subschemas being applied, so deep values and recursion
+    /// through `$dynamicRef` can't overflow the stack. `DEFAULT_MAX_DEPTH` by default.
+    pub fn max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    pub fn depth_limit(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Whether validations track entering the schema resource `id` for `$dynamicRef` and
+    /// `$recursiveRef`, which only resources of documents that lead to one of those need.
+    pub fn tracks_dynamic_scope(&self, id: &url::Url) -> bool {
+        self.dynamic_resources.contains(id.as_str())
+    }
+
+    /// Whether a schema in this scope has `unevaluatedProperties` or `unevaluatedItems`.
+    /// Validations only track the properties and items their subschemas evaluate for those.
+    pub fn tracks_evaluated(&self) -> bool {
+        self.unevaluated
+    }
+
+    fn dialect(&self, metaschema: Option<url::Url>) -> Result<Dialect<V>, schema::SchemaError>
+    where
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Debug
+            + std::string::ToString,
+    {
+        let default = &self.dialects[schema::Draft::Draft7.metaschema()];
+
+        if metaschema.is_none() {
+            return Ok(default.clone());
+        }
+
+        let metaschema = metaschema.unwrap();
+        let (key, _) = helpers::serialize_schema_path(&metaschema);
+
+        if let Some(dialect) = self.dialects.get(&key) {
+            return Ok(dialect.clone());
+        }
+
+        if let Some(dialect) = self.vocabulary_dialect(&metaschema)? {
+            return Ok(dialect);
+        }
+
+        if self.ban_unknown_dialects {
+            Err(schema::SchemaError::UnknownDialect(
+                metaschema.into_string(),
+            ))
+        } else {
+            Ok(default.clone())
+        }
+    }
+
+    /// Builds the dialect of a metaschema compiled into this scope from the vocabularies listed in
+    /// its `$vocabulary`.
+    fn vocabulary_dialect(
+        &self,
+        metaschema: &url::Url,
+    ) -> Result<Option<Dialect<V>>, schema::SchemaError>
+    where
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Debug
+            + std::string::ToString,
+    {
+        let schema = self.resolve(metaschema);
+        if schema.is_none() {
+            return Ok(None);
+        }
+
+        let schema = schema.unwrap();
+        let source = schema.source();
+        let vocabularies = source.get("$vocabulary");
+        if vocabularies.is_none() {
+            return Ok(None);
+        }
+
+        let malformed = || schema::SchemaError::Malformed {
+            path: "$vocabulary".to_string(),
+            detail: "The value of $vocabulary must be an object with boolean values".to_string(),
+        };
+
+        // In a fixed order, so format-assertion takes over `format` from format-annotation
+        let mut vocabularies = vocabularies
+            .unwrap()
+            .as_object()
+            .ok_or_else(malformed)?
+            .iter()
+            .collect::<Vec<_>>();
+        vocabularies.sort_by_key(|(vocabulary, _)| *vocabulary);
+
+        let mut keywords = HashMap::new();
+        for (vocabulary, required) in vocabularies {
+            let required = required.as_bool().ok_or_else(malformed)?;
+            let vocabulary =
+                url::Url::parse(vocabulary).map_err(schema::SchemaError::UrlParseError)?;
+
+            match self.vocabularies.get(vocabulary.as_str()) {
+                Some(vocabulary) => keywords.extend(
+                    vocabulary
+                        .iter()
+                        .map(|(key, consumer)| (key.clone(), consumer.clone())),
+                ),
+                None if required => {
+                    return Err(schema::SchemaError::UnknownVocabulary(
+                        vocabulary.into_string(),
+                    ))
+                }
+                None => (),
+            }
+        }
+
+        // Vocabularies only exist since draft 2019-09, which identifies schemas with `$id`
+        let draft = helpers::parse_url_key("$schema", source)?
+            .and_then(|url| {
+                let (key, _) = helpers::serialize_schema_path(&url);
+                self.dialects.get(&key).map(|dialect| dialect.draft)
+            })
+            .unwrap_or(schema::Draft::Draft2020_12);
+
+        Ok(Some(Dialect { draft, keywords }))
+    }
+
+    pub fn resolve(&self, id: &url::Url) -> Option<schema::ScopedSchema<'_, '_, V>>
+    where
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Debug
+            + std::string::ToString,
+    {
+        self.find(id)
+            .map(|schema| schema::ScopedSchema::new(self, schema))
+    }
+
+    /// The schema a link points at, by its index if it is linked. Validators follow their
+    /// subschemas with this.
+    pub fn follow(&self, link: &schema::Link) -> Option<schema::ScopedSchema<'_, '_, V>>
+    where
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Debug
+            + std::string::ToString,
+    {
+        match link.target() {
+            Some(target) => Some(schema::ScopedSchema::new(self, &self.targets[target])),
+            None => self.resolve(link.url()),
+        }
+    }
+
+    fn find(&self, id: &url::Url) -> Option<&Arc<schema::Schema<V>>>
     where
         <V as Value>::Key: std::borrow::Borrow<str>
             + std::hash::Hash
@@ -57,9 +420,8 @@ where
 
         let schema = self.schemes.get(&schema_path).or_else(|| {
             for (_, schema) in self.schemes.iter() {
-                let internal_schema = schema.resolve(schema_path.as_ref());
-                if internal_schema.is_some() {
-                    return internal_schema;
+                if let Some(internal_schema) = schema.resolve_shared(schema_path.as_ref()) {
+                    return Some(internal_schema);
                 }
             }
 
@@ -67,13 +429,106 @@ where
         });
 
         schema.and_then(|schema| match fragment {
-            Some(ref fragment) => schema
-                .resolve_fragment(fragment)
-                .map(|schema| schema::ScopedSchema::new(self, &*schema)),
-            None => Some(schema::ScopedSchema::new(self, &*schema)),
+            Some(ref fragment) => schema.resolve_fragment_shared(fragment),
+            None => Some(schema),
         })
     }
 
+    /// Links the subschema URLs of the schemas in this scope that aren't linked yet to the
+    /// schemas they point at, which stay in `targets` from then on.
+    fn link(&mut self)
+    where
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Debug
+            + std::string::ToString,
+    {
+        let links = self
+            .schemes
+            .values()
+            .flat_map(|schema| schema.links().iter())
+            .filter(|link| link.target().is_none())
+            .cloned()
+            .collect::<Vec<schema::Link>>();
+
+        // Schemas in a scope aren't replaced, so a URL keeps its target
+        for link in links {
+            let key = link.url().to_string();
+            let index = match self.target_indexes.get(&key) {
+                Some(&index) => index,
+                None => {
+                    let target = self.find(link.url()).cloned();
+                    if target.is_none() {
+                        continue;
+                    }
+
+                    self.targets.push(target.unwrap());
+                    self.target_indexes.insert(key, self.targets.len() - 1);
+                    self.targets.len() - 1
+                }
+            };
+            link.link(index);
+        }
+
+        self.dynamic_resources = self.dynamic_resources();
+    }
+
+    /// The ids of the schema resources in documents with a `$dynamicRef` or `$recursiveRef`, or
+    /// with links to a document that leads to one.
+    fn dynamic_resources(&self) -> HashSet<String>
+    where
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Debug
+            + std::string::ToString,
+    {
+        let mut dynamic = self
+            .schemes
+            .iter()
+            .filter(|(_, schema)| schema.has_dynamic_refs())
+            .map(|(key, _)| key.clone())
+            .collect::<HashSet<String>>();
+
+        let documents = self
+            .schemes
+            .iter()
+            .filter(|(key, _)| !dynamic.contains(*key))
+            .map(|(key, schema)| {
+                let linked = schema
+                    .links()
+                    .iter()
+                    .filter_map(|link| self.document_key(link.url()))
+                    .collect::<HashSet<String>>();
+                (key.clone(), linked)
+            })
+            .collect::<Vec<(String, HashSet<String>)>>();
+
+        // Until no more documents lead to the dynamic ones
+        loop {
+            let leading = documents
+                .iter()
+                .filter(|(key, linked)| {
+                    !dynamic.contains(key) && linked.iter().any(|key| dynamic.contains(key))
+                })
+                .map(|(key, _)| key.clone())
+                .collect::<Vec<String>>();
+            if leading.is_empty() {
+                break;
+            }
+            dynamic.extend(leading);
+        }
+
+        dynamic
+            .iter()
+            .flat_map(|key| self.schemes[key].resource_ids())
+            .map(|id| id.to_string())
+            .collect()
+    }
+
     pub fn compile_and_return<'scope>(
         &'scope mut self,
         def: OwnedValue,
@@ -82,43 +537,1633 @@ where
     where
         V: Value + std::convert::From<simd_json::value::owned::Value> + std::clone::Clone,
         <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
             + std::convert::AsRef<str>
             + std::fmt::Display
             + std::fmt::Debug,
     {
-        let schema = schema::compile(
-            def,
-            None,
-            schema::CompilationSettings::new(self.keywords.clone(), ban_unknown),
-        )?;
-        self.add_and_return(schema.id.clone().as_ref().unwrap(), schema)
+        if self.validate_schemas {
+            self.load_metaschemas()?;
+        }
+
+        let schema = self.compile(def, None, ban_unknown)?;
+        let references = schema
+            .references()
+            .into_iter()
+            .cloned()
+            .collect::<Vec<url::Url>>();
+        let key = self.add(schema.id.clone().as_ref().unwrap(), schema)?;
+
+        let mut added = vec![key.clone()];
+        let result = self
+            .resolve_references(references, ban_unknown, &mut added)
+            .and_then(|_| self.check_cycles(&added));
+        if let Err(error) = result {
+            for key in added.iter() {
+                self.schemes.remove(key);
+            }
+            return Err(error);
+        }
+        self.link();
+
+        Ok(schema::ScopedSchema::new(self, &self.schemes[&key]))
     }
 
-    fn add_and_return<'scope>(
-        &'scope mut self,
-        id: &url::Url,
-        schema: schema::Schema<V>,
-    ) -> Result<schema::ScopedSchema<'scope, 'scope, V>, schema::SchemaError> {
-        let (id_str, fragment) = helpers::serialize_schema_path(id);
-        dbg!(id_str.clone());
-        dbg!(fragment.clone());
+    /// Compiles every `.json` schema under `root` into this scope. A schema without `$id` is
+    /// identified by its path relative to `root` under `base`, which a relative `$id` resolves
+    /// against. Schemas are compiled after the schemas of the directory their `$schema` names,
+    /// and their `$ref`s are resolved once all of them are in this scope. Returns the ids of the
+    /// schemas in the order they were compiled. Stops at the first file that can't be read or
+    /// compiled, which the error names, without looking at the rest, since a schema compiled
+    /// after a failed one may only fail because of it; none of the schemas are kept then.
+    pub fn load_directory<P>(
+        &mut self,
+        root: P,
+        base: &url::Url,
+        ban_unknown: bool,
+    ) -> Result<Vec<url::Url>, loader::LoadError>
+    where
+        P: AsRef<path::Path>,
+        V: Value + std::convert::From<simd_json::value::owned::Value> + std::clone::Clone,
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Display
+            + std::fmt::Debug,
+    {
+        let root = root.as_ref();
 
-        if fragment.is_some() {
-            return Err(schema::SchemaError::WrongId);
+        if self.validate_schemas {
+            self.load_metaschemas()
+                .map_err(|error| loader::LoadError::Schema {
+                    file: root.to_path_buf(),
+                    error,
+                })?;
         }
 
-        if !self.schemes.contains_key(&id_str) {
-            self.schemes.insert(id_str.clone(), schema);
-            Ok(schema::ScopedSchema::new(self, &self.schemes[&id_str]))
+        let mut documents = vec![];
+        for file in loader::json_files(root)? {
+            let def = loader::read(&file)?;
+            let id = match self.document_id(&def, base, root, &file) {
+                Ok(id) => id,
+                Err(error) => return Err(loader::LoadError::Schema { file, error }),
+            };
+            documents.push(Some((file, id, def)));
+        }
+
+        let mut added = vec![];
+        let result = self.load_documents(documents, ban_unknown, &mut added);
+        if result.is_err() {
+            for key in added.iter() {
+                self.schemes.remove(key);
+            }
         } else {
-            Err(schema::SchemaError::IdConflicts)
+            self.link();
+        }
+
+        result
+    }
+
+    /// The id of a schema file, see `load_directory`.
+    fn document_id(
+        &self,
+        def: &OwnedValue,
+        base: &url::Url,
+        root: &path::Path,
+        file: &path::Path,
+    ) -> Result<url::Url, schema::SchemaError> {
+        let file_url = loader::file_url(base, root, file).ok_or(schema::SchemaError::WrongId)?;
+
+        // Custom metaschemas of the directory identify their schemas with `$id`
+        let draft = helpers::parse_url_key("$schema", def)?
+            .and_then(|url| {
+                let (key, _) = helpers::serialize_schema_path(&url);
+                self.dialects.get(&key).map(|dialect| dialect.draft)
+            })
+            .unwrap_or(schema::Draft::Draft7);
+
+        Ok(helpers::parse_url_key_with_base(draft.id_key(), def, &file_url)?.unwrap_or(file_url))
+    }
+
+    fn load_documents(
+        &mut self,
+        mut documents: Vec<Option<(path::PathBuf, url::Url, OwnedValue)>>,
+        ban_unknown: bool,
+        added: &mut Vec<String>,
+    ) -> Result<Vec<url::Url>, loader::LoadError>
+    where
+        V: Value + std::convert::From<simd_json::value::owned::Value> + std::clone::Clone,
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Display
+            + std::fmt::Debug,
+    {
+        let keys = documents
+            .iter()
+            .map(|document| {
+                let (_, id, _) = document.as_ref().unwrap();
+                helpers::serialize_schema_path(id).0
+            })
+            .collect::<Vec<String>>();
+
+        // A schema whose `$schema` names another schema of the directory depends on it
+        let dependencies = documents
+            .iter()
+            .map(|document| {
+                let (_, _, def) = document.as_ref().unwrap();
+                helpers::parse_url_key("$schema", def)
+                    .ok()
+                    .flatten()
+                    .and_then(|url| {
+                        let (key, _) = helpers::serialize_schema_path(&url);
+                        keys.iter().position(|other| *other == key)
+                    })
+            })
+            .collect::<Vec<Option<usize>>>();
+
+        let mut order = vec![];
+        for index in 0..documents.len() {
+            let mut chain = vec![];
+            let mut next = Some(index);
+            while let Some(current) = next {
+                if order.contains(&current) || chain.contains(&current) {
+                    break;
+                }
+                chain.push(current);
+                next = dependencies[current];
+            }
+            order.extend(chain.into_iter().rev());
+        }
+
+        let mut ids = vec![];
+        let mut references = vec![];
+        for index in order {
+            let (file, id, def) = documents[index].take().unwrap();
+            let schema = match self.compile(def, Some(id.clone()), ban_unknown) {
+                Ok(schema) => schema,
+                Err(error) => return Err(loader::LoadError::Schema { file, error }),
+            };
+
+            let schema_references = schema
+                .references()
+                .into_iter()
+                .cloned()
+                .collect::<Vec<url::Url>>();
+            match self.add(&id, schema) {
+                Ok(key) => {
+                    added.push(key.clone());
+                    references.push((file, key, schema_references));
+                }
+                Err(error) => return Err(loader::LoadError::Schema { file, error }),
+            }
+            ids.push(id);
+        }
+
+        for (file, key, references) in references {
+            let fetched = added.len();
+            let result = self
+                .resolve_references(references, ban_unknown, added)
+                .and_then(|_| {
+                    let mut keys = vec![key];
+                    keys.extend(added[fetched..].iter().cloned());
+                    self.check_cycles(&keys)
+                });
+            if let Err(error) = result {
+                return Err(loader::LoadError::Schema { file, error });
+            }
+        }
+
+        Ok(ids)
+    }
+
+    /// Compiles a schema with the dialect named by its `$schema`, checking it against its
+    /// metaschema if asked to.
+    fn compile(
+        &self,
+        def: OwnedValue,
+        external_id: Option<url::Url>,
+        ban_unknown: bool,
+    ) -> Result<schema::Schema<V>, schema::SchemaError>
+    where
+        V: Value + std::convert::From<simd_json::value::owned::Value> + std::clone::Clone,
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Display
+            + std::fmt::Debug,
+    {
+        let metaschema = helpers::parse_url_key("$schema", &def)?;
+        let dialect = self.dialect(metaschema.clone())?;
+
+        // The bundled metaschemas themselves aren't checked
+        if self.validate_schemas && self.metaschemas_loaded {
+            self.check_schema(&def, metaschema, dialect.draft)?;
         }
+
+        let mut settings = schema::CompilationSettings::new(dialect.keywords, ban_unknown)
+            .with_draft(dialect.draft)
+            .with_regex_engine(self.regex_engine.clone())
+            .with_regex_limits(self.regex_limits);
+        if let Some(ref format_mode) = self.format_mode {
+            settings = settings.with_format_mode(format_mode.clone());
+        }
+
+        schema::compile(def, external_id, settings)
     }
 
-    pub fn add_keyword<T>(&mut self, keys: Vec<String>, keyword: T)
+    /// Compiles the schemas `references` point at that aren't in this scope from the resolver,
+    /// and the ones those point at in turn. The keys of the compiled schemas are added to `added`.
+    fn resolve_references(
+        &mut self,
+        mut references: Vec<url::Url>,
+        ban_unknown: bool,
+        added: &mut Vec<String>,
+    ) -> Result<(), schema::SchemaError>
     where
-        T: keywords::Keyword<V>,
+        V: Value + std::convert::From<simd_json::value::owned::Value> + std::clone::Clone,
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Display
+            + std::fmt::Debug,
     {
-        keywords::decouple_keyword((keys, Box::new(keyword)), &mut self.keywords);
+        while let Some(url) = references.pop() {
+            if self.resolve(&url).is_some() {
+                continue;
+            }
+
+            let mut document = url.clone();
+            document.set_fragment(None);
+
+            // A fragment missing from a document in this scope can't be resolved otherwise
+            let def = match self.resolver {
+                Some(ref resolver) if self.resolve(&document).is_none() => {
+                    resolver.resolve(&document)
+                }
+                _ => None,
+            };
+
+            if def.is_none() {
+                if self.ban_unresolved_refs {
+                    return Err(schema::SchemaError::UnresolvedRef(url.into_string()));
+                }
+                continue;
+            }
+
+            let schema = self.compile(def.unwrap(), Some(document.clone()), ban_unknown)?;
+            references.extend(schema.references().into_iter().cloned());
+            added.push(self.add(&document, schema)?);
+
+            // Checks the fragment of `url` within the new document
+            references.push(url);
+        }
+
+        Ok(())
+    }
+
+    /// Bundles the schema `id` names with the schemas of other documents its `$ref`s reach into
+    /// one document, which validates the same way without them. They are embedded under `$defs`,
+    /// or `definitions` before draft 2019-09, keyed and identified by their absolute ids, and
+    /// the `$ref`s pointing at them are rewritten to those ids.
+    pub fn bundle(&self, id: &url::Url) -> Result<OwnedValue, schema::SchemaError>
+    where
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Debug
+            + std::string::ToString,
+    {
+        let (key, fragment) = helpers::serialize_schema_path(id);
+        if fragment.is_some() {
+            return Err(schema::SchemaError::WrongId);
+        }
+
+        let root = self.schemes.get(&key);
+        if root.is_none() {
+            return Err(schema::SchemaError::UnresolvedRef(id.as_str().to_string()));
+        }
+
+        let root = root.unwrap();
+        let draft = self.dialect(root.metaschema().cloned())?.draft;
+        let root_id = root.id.as_ref().unwrap();
+
+        let mut documents = vec![key.clone()];
+        let mut bundle = self.bundle_source(root, root.source(), root_id, &key, &mut documents);
+
+        // A relative id of the root or one it was compiled with doesn't resolve in the bundle,
+        // and generated ids can't be referenced
+        if root_id.scheme() != "json-schema" {
+            bundle
+                .as_object_mut()
+                .unwrap()
+                .insert(draft.id_key().into(), root_id.as_str().into());
+        }
+
+        let mut embedded = vec![];
+        let mut next = 1;
+        while next < documents.len() {
+            let key = documents[next].clone();
+            let document = &self.schemes[&key];
+            let document_id = document.id.as_ref().unwrap();
+            let mut source = self.bundle_source(
+                document,
+                document.source(),
+                document_id,
+                &key,
+                &mut documents,
+            );
+            source
+                .as_object_mut()
+                .unwrap()
+                .insert(draft.id_key().into(), document_id.as_str().into());
+            embedded.push((document_id.as_str().to_string(), source));
+            next += 1;
+        }
+
+        if embedded.is_empty() {
+            return Ok(bundle);
+        }
+
+        let key = match draft {
+            schema::Draft::Draft2019_09 | schema::Draft::Draft2020_12 => "$defs",
+            _ => "definitions",
+        };
+
+        let definitions = bundle
+            .as_object_mut()
+            .unwrap()
+            .entry(key.to_string())
+            .or_insert_with(OwnedValue::object)
+            .as_object_mut();
+        if definitions.is_none() {
+            return Err(schema::SchemaError::Malformed {
+                path: key.to_string(),
+                detail: format!("The value of `{}` must be an object", key),
+            });
+        }
+
+        let definitions = definitions.unwrap();
+        for (key, source) in embedded {
+            definitions.insert(key, source);
+        }
+
+        Ok(bundle)
+    }
+
+    /// Copies the schema `id` points at with every `$ref` replaced by the schema it points at,
+    /// except the ones that point at a schema they are in, which are reported. Before draft
+    /// 2019-09 a `$ref` replaces its whole schema, since then it becomes an item of its `allOf`
+    /// unless it is the only keyword. The `$id` of the replacing schemas is dropped, and the
+    /// `$ref`s left in place are made absolute where the base URL differs.
+    pub fn dereference(&self, id: &url::Url) -> Result<Dereferenced, schema::SchemaError>
+    where
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Debug
+            + std::string::ToString,
+    {
+        let schema = self.find(id);
+        let document = self.document_key(id);
+        let location = self.schema_location(id);
+        if schema.is_none() || document.is_none() || location.is_none() {
+            return Err(schema::SchemaError::UnresolvedRef(id.as_str().to_string()));
+        }
+
+        let draft = self
+            .dialect(self.schemes[&document.unwrap()].metaschema().cloned())?
+            .draft;
+        let mut base = id.clone();
+        base.set_fragment(None);
+
+        let schema = schema.unwrap();
+        let mut dereferencing = Dereferencing {
+            draft,
+            base: schema.id.clone().unwrap_or_else(|| base.clone()),
+            schemas: vec![],
+            path: vec![],
+            recursive: vec![],
+        };
+        let source = self.dereference_source(
+            schema,
+            location.unwrap(),
+            schema.source(),
+            &base,
+            &mut dereferencing,
+        );
+
+        Ok(Dereferenced {
+            schema: source,
+            recursive: dereferencing.recursive,
+        })
+    }
+
+    // Copies `source`, the JSON of `schema` at `location`, with its `$ref`s dereferenced
+    fn dereference_source(
+        &self,
+        schema: &schema::Schema<V>,
+        location: String,
+        source: &OwnedValue,
+        base: &url::Url,
+        dereferencing: &mut Dereferencing,
+    ) -> OwnedValue
+    where
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Debug
+            + std::string::ToString,
+    {
+        let base = schema.id.as_ref().unwrap_or(base);
+        let mut source = source.clone();
+        dereferencing.schemas.push(location);
+
+        let url = source
+            .get("$ref")
+            .and_then(|ref_| ref_.as_str())
+            .and_then(|ref_| url::Url::options().base_url(Some(base)).parse(ref_).ok())
+            .filter(|url| schema.own_references().contains(&url));
+        let target = url
+            .as_ref()
+            .and_then(|url| Some((self.find(url)?, self.schema_location(url)?)));
+        if let Some((target, location)) = target {
+            let url = url.unwrap();
+
+            if dereferencing.schemas.contains(&location) {
+                if base != &dereferencing.base {
+                    source
+                        .as_object_mut()
+                        .unwrap()
+                        .insert("$ref".into(), url.as_str().into());
+                }
+                dereferencing
+                    .recursive
+                    .push(helpers::pointer(&dereferencing.path));
+            } else {
+                let legacy = !matches!(
+                    dereferencing.draft,
+                    schema::Draft::Draft2019_09 | schema::Draft::Draft2020_12
+                );
+                let siblings = source.as_object().unwrap().len() > 1;
+                // The position of the schema in `allOf`, unless that isn't an array
+                let index = match source.get("allOf") {
+                    Some(all_of) => all_of.as_array().map(|all_of| all_of.len()),
+                    None => Some(0),
+                };
+
+                if legacy || !siblings {
+                    let inlined = self.dereference_target(target, location, &url, dereferencing);
+                    dereferencing.schemas.pop();
+                    return inlined;
+                }
+
+                if let Some(index) = index {
+                    source = self.dereference_children(schema, source, base, dereferencing);
+
+                    dereferencing.path.push("allOf".to_string());
+                    dereferencing.path.push(index.to_string());
+                    let inlined = self.dereference_target(target, location, &url, dereferencing);
+                    dereferencing.path.truncate(dereferencing.path.len() - 2);
+
+                    let object = source.as_object_mut().unwrap();
+                    object.remove("$ref");
+                    object
+                        .entry("allOf".to_string())
+                        .or_insert_with(OwnedValue::array)
+                        .as_array_mut()
+                        .unwrap()
+                        .push(inlined);
+
+                    dereferencing.schemas.pop();
+                    return source;
+                }
+            }
+        }
+
+        let source = self.dereference_children(schema, source, base, dereferencing);
+        dereferencing.schemas.pop();
+
+        source
+    }
+
+    // Dereferences the schema a `$ref` to `url` points at, for the `$ref` to be replaced with it
+    fn dereference_target(
+        &self,
+        target: &schema::Schema<V>,
+        location: String,
+        url: &url::Url,
+        dereferencing: &mut Dereferencing,
+    ) -> OwnedValue
+    where
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Debug
+            + std::string::ToString,
+    {
+        let mut base = url.clone();
+        base.set_fragment(None);
+
+        let mut inlined =
+            self.dereference_source(target, location, target.source(), &base, dereferencing);
+        if inlined.is_object() {
+            inlined
+                .as_object_mut()
+                .unwrap()
+                .remove(dereferencing.draft.id_key());
+        }
+
+        inlined
+    }
+
+    // Dereferences the values of `source` that are subschemas of `schema`
+    fn dereference_children(
+        &self,
+        schema: &schema::Schema<V>,
+        mut source: OwnedValue,
+        base: &url::Url,
+        dereferencing: &mut Dereferencing,
+    ) -> OwnedValue
+    where
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Debug
+            + std::string::ToString,
+    {
+        // The children of the schema walked last
+        let parent = dereferencing.schemas.last().unwrap().clone();
+        if source.is_object() {
+            for (key, value) in source.as_object_mut().unwrap().iter_mut() {
+                let child = schema
+                    .child(key)
+                    .filter(|_| value.is_object() || value.is_array());
+                if let Some(child) = child {
+                    let location = [parent.as_str(), helpers::encode(key).as_str()].join("/");
+                    dereferencing.path.push(key.clone());
+                    *value = self.dereference_source(child, location, value, base, dereferencing);
+                    dereferencing.path.pop();
+                }
+            }
+        } else if source.is_array() {
+            for (index, value) in source.as_array_mut().unwrap().iter_mut().enumerate() {
+                let child = schema
+                    .child(&index.to_string())
+                    .filter(|_| value.is_object() || value.is_array());
+                if let Some(child) = child {
+                    let location = [parent.clone(), index.to_string()].join("/");
+                    dereferencing.path.push(index.to_string());
+                    *value = self.dereference_source(child, location, value, base, dereferencing);
+                    dereferencing.path.pop();
+                }
+            }
+        }
+
+        source
+    }
+
+    /// The URL of the schema `url` points at by the key of its document and the escaped keys on
+    /// the path from there, which is the same whichever id, anchor or JSON pointer `url` names
+    /// it by. Walks over the schemas of this scope tell them apart by it.
+    fn schema_location(&self, url: &url::Url) -> Option<String>
+    where
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Debug
+            + std::string::ToString,
+    {
+        let (key, fragment) = helpers::serialize_schema_path(url);
+        let (document, mut path) = if self.schemes.contains_key(&key) {
+            (key, vec![])
+        } else {
+            self.schemes.iter().find_map(|(document, schema)| {
+                let path = schema.resolve_path(&key)?;
+                Some((
+                    document.clone(),
+                    path.iter().map(|key| helpers::encode(key)).collect(),
+                ))
+            })?
+        };
+
+        if let Some(fragment) = fragment {
+            path.extend(
+                fragment[1..]
+                    .split('/')
+                    .map(|key| helpers::encode(&helpers::decode(key))),
+            );
+        }
+
+        Some([vec![document], path].concat().join("/"))
+    }
+
+    /// The key of the document in this scope `url` points into.
+    fn document_key(&self, url: &url::Url) -> Option<String>
+    where
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Debug
+            + std::string::ToString,
+    {
+        let (key, _) = helpers::serialize_schema_path(url);
+        if self.schemes.contains_key(&key) {
+            return Some(key);
+        }
+
+        self.schemes
+            .iter()
+            .find(|(_, schema)| schema.resolve(&key).is_some())
+            .map(|(document, _)| document.clone())
+    }
+
+    /// Copies `source`, the JSON of `schema` in `document`, with the `$ref`s to other documents
+    /// rewritten to absolute URLs, and adds those documents to `documents`.
+    fn bundle_source(
+        &self,
+        schema: &schema::Schema<V>,
+        source: &OwnedValue,
+        base: &url::Url,
+        document: &str,
+        documents: &mut Vec<String>,
+    ) -> OwnedValue
+    where
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Debug
+            + std::string::ToString,
+    {
+        let base = schema.id.as_ref().unwrap_or(base);
+        let mut source = source.clone();
+
+        if source.is_object() {
+            let references = schema.own_references();
+
+            for (key, value) in source.as_object_mut().unwrap().iter_mut() {
+                let child = schema
+                    .child(key)
+                    .filter(|_| value.is_object() || value.is_array());
+                if let Some(child) = child {
+                    *value = self.bundle_source(child, value, base, document, documents);
+                    continue;
+                }
+
+                if key != "$ref" && key != "$dynamicRef" {
+                    continue;
+                }
+
+                let url = value
+                    .as_str()
+                    .and_then(|ref_| url::Url::options().base_url(Some(base)).parse(ref_).ok());
+                if url.is_none() || !references.contains(&url.as_ref().unwrap()) {
+                    continue;
+                }
+
+                let url = url.unwrap();
+                let target = self.document_key(&url);
+                if let Some(target) = target.filter(|target| target != document) {
+                    if !documents.contains(&target) {
+                        documents.push(target);
+                    }
+                    *value = url.as_str().into();
+                }
+            }
+        } else if source.is_array() {
+            for (index, value) in source.as_array_mut().unwrap().iter_mut().enumerate() {
+                let child = schema
+                    .child(&index.to_string())
+                    .filter(|_| value.is_object() || value.is_array());
+                if let Some(child) = child {
+                    *value = self.bundle_source(child, value, base, document, documents);
+                }
+            }
+        }
+
+        source
+    }
+
+    /// Fails with `SchemaError::RefCycle` if a schema of the documents under `keys` reaches itself
+    /// through subschemas applied to the value in place, which validation would follow forever.
+    fn check_cycles(&self, keys: &[String]) -> Result<(), schema::SchemaError>
+    where
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Debug
+            + std::string::ToString,
+    {
+        let mut checked = HashSet::new();
+
+        for key in keys.iter() {
+            let mut schemas = vec![(self.schemes[key].as_ref(), key.clone())];
+            while let Some((schema, location)) = schemas.pop() {
+                schemas.extend(
+                    schema
+                        .subschemas()
+                        .map(|(key, child)| (child, [location.as_str(), key].join("/"))),
+                );
+                self.check_cycle(schema, location, &mut vec![], &mut checked)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Walks the in-place subschemas of `schema` at `location` depth-first, `path` holding the
+    // locations of the ones walked into, see `schema_location`
+    fn check_cycle(
+        &self,
+        schema: &schema::Schema<V>,
+        location: String,
+        path: &mut Vec<String>,
+        checked: &mut HashSet<String>,
+    ) -> Result<(), schema::SchemaError>
+    where
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Debug
+            + std::string::ToString,
+    {
+        if checked.contains(&location) {
+            return Ok(());
+        }
+
+        path.push(location);
+        for link in schema.in_place() {
+            let (target, target_location) =
+                match (self.find(link.url()), self.schema_location(link.url())) {
+                    (Some(target), Some(target_location)) => (target, target_location),
+                    _ => continue,
+                };
+
+            if path.contains(&target_location) {
+                return Err(schema::SchemaError::RefCycle(
+                    link.url().as_str().to_string(),
+                ));
+            }
+            self.check_cycle(target, target_location, path, checked)?;
+        }
+        checked.extend(path.pop());
+
+        Ok(())
+    }
+
+    /// Compiles the bundled metaschemas into this scope, once.
+    fn load_metaschemas(&mut self) -> Result<(), schema::SchemaError>
+    where
+        V: Value + std::convert::From<simd_json::value::owned::Value> + std::clone::Clone,
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Display
+            + std::fmt::Debug,
+    {
+        if self.metaschemas_loaded {
+            return Ok(());
+        }
+
+        for def in metaschemas::sources() {
+            let schema = self.compile(def, None, false)?;
+            match self.add(schema.id.clone().as_ref().unwrap(), schema) {
+                // A metaschema compiled into this scope before takes precedence
+                Ok(_) | Err(schema::SchemaError::IdConflicts) => (),
+                Err(error) => return Err(error),
+            }
+        }
+        self.metaschemas_loaded = true;
+        self.link();
+
+        Ok(())
+    }
+
+    /// Validates a schema against the metaschema named by its `$schema`.
+    fn check_schema(
+        &self,
+        def: &OwnedValue,
+        metaschema: Option<url::Url>,
+        draft: schema::Draft,
+    ) -> Result<(), schema::SchemaError>
+    where
+        V: Value + std::convert::From<simd_json::value::owned::Value> + std::clone::Clone,
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Display
+            + std::fmt::Debug,
+    {
+        let schema = metaschema.and_then(|url| self.resolve(&url)).or_else(|| {
+            url::Url::parse(draft.metaschema())
+                .ok()
+                .and_then(|url| self.resolve(&url))
+        });
+
+        if schema.is_none() {
+            return Ok(());
+        }
+
+        let state = schema.unwrap().validate_in(&V::from(def.clone()), "");
+        if state.is_valid() {
+            Ok(())
+        } else {
+            Err(schema::SchemaError::Invalid(state.errors))
+        }
+    }
+
+    fn add(
+        &mut self,
+        id: &url::Url,
+        schema: schema::Schema<V>,
+    ) -> Result<String, schema::SchemaError>
+    where
+        <V as Value>::Key: std::borrow::Borrow<str> + std::convert::AsRef<str> + std::fmt::Debug,
+    {
+        let (id_str, fragment) = helpers::serialize_schema_path(id);
+
+        if fragment.is_some() {
+            return Err(schema::SchemaError::WrongId);
+        }
+
+        if !self.schemes.contains_key(&id_str) {
+            self.unevaluated = self.unevaluated || schema.has_deferred();
+            self.schemes.insert(id_str.clone(), Arc::new(schema));
+            Ok(id_str)
+        } else {
+            Err(schema::SchemaError::IdConflicts)
+        }
+    }
+
+    /// Adds a keyword to the dialect of the metaschema `uri`, or else to the vocabulary `uri`,
+    /// which custom metaschemas select in their `$vocabulary`. Schemas of other dialects don't
+    /// run it.
+    pub fn add_keyword<T>(&mut self, uri: &url::Url, keys: Vec<String>, keyword: T)
+    where
+        T: keywords::Keyword<V> + 'static,
+    {
+        let consumer = Arc::new(keywords::KeywordConsumer {
+            keys,
+            keyword: Box::new(keyword),
+        });
+
+        let (key, _) = helpers::serialize_schema_path(uri);
+        let dialect_keys = [key.clone(), other_scheme(&key)];
+        let mut maps = self
+            .dialects
+            .iter_mut()
+            .filter(|(key, _)| dialect_keys.contains(key))
+            .map(|(_, dialect)| &mut dialect.keywords)
+            .collect::<Vec<&mut keywords::KeywordMap<V>>>();
+        if maps.is_empty() {
+            maps.push(
+                self.vocabularies
+                    .entry(uri.as_str().to_string())
+                    .or_default(),
+            );
+        }
+
+        for map in maps {
+            for key in consumer.keys.iter() {
+                map.insert(key.to_string(), consumer.clone());
+            }
+        }
+    }
+}
+
+// Metaschemas are commonly referenced with either scheme
+fn other_scheme(uri: &str) -> String {
+    if uri.starts_with("https:") {
+        uri.replacen("https:", "http:", 1)
+    } else {
+        uri.replacen("http:", "https:", 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::keywords;
+    use super::super::schema;
+    use super::super::validators;
+    use super::Scope;
+    use simd_json::json;
+    use simd_json::value::owned::Value as OwnedValue;
+
+    #[test]
+    fn dialect_follows_schema() {
+        let mut scope = Scope::<OwnedValue>::new();
+        let schema = json!({
+            "$schema": "http://json-schema.org/draft-06/schema#",
+            "if": { "type": "string" },
+            "then": { "const": "a" }
+        });
+        assert!(scope
+            .compile_and_return(schema, false)
+            .unwrap()
+            .validate(&json!("ab"))
+            .is_valid());
+
+        let mut scope = Scope::<OwnedValue>::new();
+        let schema = json!({
+            "$schema": "https://json-schema.org/draft-07/schema#",
+            "if": { "type": "string" },
+            "then": { "const": "a" }
+        });
+        assert!(!scope
+            .compile_and_return(schema, false)
+            .unwrap()
+            .validate(&json!("ab"))
+            .is_valid());
+    }
+
+    #[test]
+    fn unknown_dialect_falls_back_to_draft7() {
+        let mut scope = Scope::<OwnedValue>::new();
+        let schema = json!({
+            "$schema": "https://example.com/unknown",
+            "if": { "type": "string" },
+            "then": { "const": "a" }
+        });
+        assert!(!scope
+            .compile_and_return(schema, false)
+            .unwrap()
+            .validate(&json!("ab"))
+            .is_valid());
+    }
+
+    #[test]
+    fn unknown_dialect_can_be_banned() {
+        let mut scope = Scope::<OwnedValue>::new();
+        scope.ban_unknown_dialects(true);
+        let result =
+            scope.compile_and_return(json!({ "$schema": "https://example.com/unknown" }), false);
+        match result {
+            Err(schema::SchemaError::UnknownDialect(ref uri)) => {
+                assert_eq!(uri, "https://example.com/unknown")
+            }
+            _ => panic!("expected an unknown dialect error"),
+        }
+    }
+
+    #[test]
+    fn custom_dialect() {
+        let mut scope = Scope::<OwnedValue>::new();
+        scope.ban_unknown_dialects(true);
+
+        let mut map = keywords::draft2020_12::<OwnedValue>();
+        let even = |_: &OwnedValue, _: &schema::WalkContext| {
+            Ok(Some(Box::new(validators::MultipleOf { number: 2.0 })
+                as validators::BoxedValidator<OwnedValue>))
+        };
+        keywords::decouple_keyword((vec!["even".to_string()], Box::new(even)), &mut map);
+        scope.add_dialect(
+            &url::Url::parse("https://example.com/even#").unwrap(),
+            schema::Draft::Draft2020_12,
+            map,
+        );
+
+        let schema = scope
+            .compile_and_return(
+                json!({ "$schema": "https://example.com/even", "even": true }),
+                true,
+            )
+            .unwrap();
+        assert!(schema.validate(&json!(4.0)).is_valid());
+        assert!(!schema.validate(&json!(3.0)).is_valid());
+    }
+
+    fn compile_metaschema(scope: &mut Scope<OwnedValue>, id: &str, vocabulary: OwnedValue) {
+        scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "$id": id,
+                    "$vocabulary": vocabulary
+                }),
+                false,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn vocabularies_select_keywords() {
+        let mut scope = Scope::<OwnedValue>::new();
+        compile_metaschema(
+            &mut scope,
+            "https://example.com/meta/validation-only",
+            json!({
+                "https://json-schema.org/draft/2020-12/vocab/core": true,
+                "https://json-schema.org/draft/2020-12/vocab/validation": true
+            }),
+        );
+
+        let schema = scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://example.com/meta/validation-only",
+                    "type": "object",
+                    "properties": { "a": { "type": "string" } }
+                }),
+                false,
+            )
+            .unwrap();
+        assert!(schema.validate(&json!({ "a": 1 })).is_valid());
+        assert!(!schema.validate(&json!(1)).is_valid());
+    }
+
+    #[test]
+    fn custom_vocabulary() {
+        let mut scope = Scope::<OwnedValue>::new();
+
+        let mut map = keywords::KeywordMap::<OwnedValue>::new();
+        let even = |_: &OwnedValue, _: &schema::WalkContext| {
+            Ok(Some(Box::new(validators::MultipleOf { number: 2.0 })
+                as validators::BoxedValidator<OwnedValue>))
+        };
+        keywords::decouple_keyword((vec!["even".to_string()], Box::new(even)), &mut map);
+        scope.add_vocabulary(
+            &url::Url::parse("https://example.com/vocab/even").unwrap(),
+            map,
+        );
+
+        compile_metaschema(
+            &mut scope,
+            "https://example.com/meta/even",
+            json!({
+                "https://json-schema.org/draft/2020-12/vocab/core": true,
+                "https://example.com/vocab/even": true,
+                "https://example.com/vocab/optional": false
+            }),
+        );
+
+        let schema = scope
+            .compile_and_return(
+                json!({ "$schema": "https://example.com/meta/even", "even": true }),
+                true,
+            )
+            .unwrap();
+        assert!(schema.validate(&json!(4.0)).is_valid());
+        assert!(!schema.validate(&json!(3.0)).is_valid());
+    }
+
+    #[test]
+    fn keywords_are_added_to_one_dialect_or_vocabulary() {
+        let mut scope = Scope::<OwnedValue>::new();
+        let even = |_: &OwnedValue, _: &schema::WalkContext| {
+            Ok(Some(Box::new(validators::MultipleOf { number: 2.0 })
+                as validators::BoxedValidator<OwnedValue>))
+        };
+        scope.add_keyword(
+            &url::Url::parse("https://example.com/vocab/even").unwrap(),
+            vec!["even".to_string()],
+            even,
+        );
+        scope.add_keyword(
+            &url::Url::parse(schema::Draft::Draft2020_12.metaschema()).unwrap(),
+            vec!["even".to_string()],
+            even,
+        );
+
+        compile_metaschema(
+            &mut scope,
+            "https://example.com/meta/even",
+            json!({ "https://example.com/vocab/even": true }),
+        );
+        compile_metaschema(
+            &mut scope,
+            "https://example.com/meta/validation-only",
+            json!({ "https://json-schema.org/draft/2020-12/vocab/validation": true }),
+        );
+
+        let is_even = |scope: &mut Scope<OwnedValue>, metaschema: &str| {
+            let schema = scope
+                .compile_and_return(json!({ "$schema": metaschema, "even": true }), false)
+                .unwrap();
+            !schema.validate(&json!(3.0)).is_valid()
+        };
+        assert!(is_even(&mut scope, "https://example.com/meta/even"));
+        assert!(is_even(
+            &mut scope,
+            "http://json-schema.org/draft/2020-12/schema"
+        ));
+        assert!(!is_even(
+            &mut scope,
+            "https://example.com/meta/validation-only"
+        ));
+        assert!(!is_even(
+            &mut scope,
+            "https://json-schema.org/draft/2019-09/schema"
+        ));
+        assert!(!is_even(
+            &mut scope,
+            "http://json-schema.org/draft-04/schema#"
+        ));
+    }
+
+    #[test]
+    fn unknown_required_vocabulary() {
+        let mut scope = Scope::<OwnedValue>::new();
+        compile_metaschema(
+            &mut scope,
+            "https://example.com/meta/unknown",
+            json!({ "https://example.com/vocab/unknown": true }),
+        );
+
+        let result = scope.compile_and_return(
+            json!({ "$schema": "https://example.com/meta/unknown" }),
+            false,
+        );
+        match result {
+            Err(schema::SchemaError::UnknownVocabulary(ref uri)) => {
+                assert_eq!(uri, "https://example.com/vocab/unknown")
+            }
+            _ => panic!("expected an unknown vocabulary error"),
+        }
+    }
+
+    #[test]
+    fn schemas_are_validated_against_their_metaschema() {
+        let schemas = vec![
+            "http://json-schema.org/draft-04/schema#",
+            "http://json-schema.org/draft-06/schema#",
+            "http://json-schema.org/draft-07/schema#",
+            "https://json-schema.org/draft/2019-09/schema",
+            "https://json-schema.org/draft/2020-12/schema",
+        ];
+
+        for metaschema in schemas {
+            let mut scope = Scope::<OwnedValue>::new();
+            scope.validate_schemas(true);
+
+            let result = scope.compile_and_return(
+                json!({
+                    "$schema": metaschema,
+                    "type": "object",
+                    "properties": { "a": { "type": "string", "minLength": 1 } },
+                    "required": ["a"]
+                }),
+                false,
+            );
+            assert!(result.is_ok(), "{}", metaschema);
+
+            let result = scope.compile_and_return(
+                json!({
+                    "$schema": metaschema,
+                    "properties": {
+                        "a": { "type": "text" },
+                        "b": { "minLength": -1 }
+                    }
+                }),
+                false,
+            );
+            match result {
+                Err(schema::SchemaError::Invalid(ref errors)) => {
+                    let paths = errors
+                        .iter()
+                        .map(|error| error.get_path())
+                        .collect::<Vec<&str>>();
+                    assert!(paths.contains(&"/properties/a/type"), "{}", metaschema);
+                    assert!(paths.contains(&"/properties/b/minLength"), "{}", metaschema);
+                }
+                _ => panic!("expected a schema invalid against {}", metaschema),
+            }
+        }
+    }
+
+    #[test]
+    fn schemas_without_dialect_use_draft7() {
+        let mut scope = Scope::<OwnedValue>::new();
+        scope.validate_schemas(true);
+
+        let result = scope.compile_and_return(json!({ "required": "a" }), false);
+        match result {
+            Err(schema::SchemaError::Invalid(ref errors)) => {
+                assert_eq!(errors[0].get_path(), "/required")
+            }
+            _ => panic!("expected an invalid schema"),
+        }
+
+        let mut scope = Scope::<OwnedValue>::new();
+        assert!(scope
+            .compile_and_return(json!({ "required": ["a"], "items": 1 }), false)
+            .is_err());
+    }
+
+    #[test]
+    fn subschemas_are_linked() {
+        let mut scope = Scope::<OwnedValue>::new();
+        scope
+            .compile_and_return(
+                json!({
+                    "$id": "https://example.com/list",
+                    "type": "array",
+                    "items": { "$ref": "https://example.com/item" }
+                }),
+                false,
+            )
+            .unwrap();
+
+        // The item schema isn't in the scope yet
+        let list = &scope.schemes["https://example.com/list"];
+        assert_eq!(list.links().len(), 2);
+        assert!(list.links().iter().any(|link| link.target().is_none()));
+
+        scope
+            .compile_and_return(
+                json!({ "$id": "https://example.com/item", "type": "integer" }),
+                false,
+            )
+            .unwrap();
+
+        let list = &scope.schemes["https://example.com/list"];
+        assert!(list.links().iter().all(|link| link.target().is_some()));
+
+        let schema = scope
+            .resolve(&url::Url::parse("https://example.com/list").unwrap())
+            .unwrap();
+        assert!(schema.validate(&json!([1, 2])).is_valid());
+        assert!(!schema.validate(&json!([1, "2"])).is_valid());
+    }
+
+    #[test]
+    fn links_share_targets() {
+        let mut scope = Scope::<OwnedValue>::new();
+        scope
+            .compile_and_return(
+                json!({
+                    "$id": "https://example.com/pair",
+                    "properties": {
+                        "a": { "$ref": "#/definitions/id" },
+                        "b": { "$ref": "#/definitions/id" }
+                    },
+                    "definitions": { "id": { "type": "integer" } }
+                }),
+                false,
+            )
+            .unwrap();
+
+        let pair = &scope.schemes["https://example.com/pair"];
+        let urls = pair
+            .links()
+            .iter()
+            .map(|link| link.url().as_str())
+            .collect::<hashbrown::HashSet<&str>>();
+        assert_eq!(scope.targets.len(), urls.len());
+
+        let ids = pair
+            .links()
+            .iter()
+            .filter(|link| link.url().fragment() == Some("/definitions/id"))
+            .map(|link| link.target().unwrap())
+            .collect::<Vec<usize>>();
+        assert_eq!(ids.len(), 2);
+        assert_eq!(ids[0], ids[1]);
+    }
+
+    #[test]
+    fn schemas_report_their_metaschema() {
+        let mut scope = Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(
+                json!({ "$schema": "http://json-schema.org/draft-06/schema#" }),
+                false,
+            )
+            .unwrap();
+        assert_eq!(
+            schema.metaschema().map(|url| url.as_str()),
+            Some("http://json-schema.org/draft-06/schema#")
+        );
+
+        let schema = scope.compile_and_return(json!({}), false).unwrap();
+        assert!(schema.metaschema().is_none());
+    }
+
+    #[test]
+    fn ref_cycles_are_rejected() {
+        let mut scope = Scope::<OwnedValue>::new();
+        let result = scope.compile_and_return(json!({ "$ref": "#" }), false);
+        assert!(matches!(result, Err(schema::SchemaError::RefCycle(_))));
+
+        let schema = json!({
+            "properties": { "a": { "$ref": "#/definitions/a" } },
+            "definitions": {
+                "a": { "$ref": "#/definitions/b" },
+                "b": { "anyOf": [{ "type": "string" }, { "$ref": "#/definitions/a" }] }
+            }
+        });
+        let result = scope.compile_and_return(schema, false);
+        assert!(matches!(result, Err(schema::SchemaError::RefCycle(_))));
+
+        // Each step of the recursion validates a value in the previous one
+        let schema = json!({ "properties": { "next": { "$ref": "#" } } });
+        assert!(scope.compile_and_return(schema, false).is_ok());
+    }
+
+    #[test]
+    fn ref_cycles_through_ids_are_rejected() {
+        let mut scope = Scope::<OwnedValue>::new();
+        let schema = json!({
+            "$id": "https://example.com/root",
+            "definitions": {
+                "a": {
+                    "$id": "https://example.com/a",
+                    "allOf": [{ "$ref": "https://example.com/root#/definitions/a" }]
+                }
+            }
+        });
+        let result = scope.compile_and_return(schema, false);
+        assert!(matches!(result, Err(schema::SchemaError::RefCycle(_))));
+    }
+
+    #[test]
+    fn ref_cycles_across_documents_are_rejected() {
+        let mut scope = Scope::<OwnedValue>::new();
+        scope.resolver(|url: &url::Url| match url.as_str() {
+            "https://example.com/b" => Some(json!({ "allOf": [{ "$ref": "a" }] })),
+            _ => None,
+        });
+
+        let schema = json!({ "$id": "https://example.com/a", "$ref": "b" });
+        let result = scope.compile_and_return(schema, false);
+        assert!(matches!(result, Err(schema::SchemaError::RefCycle(_))));
+        assert!(scope
+            .resolve(&url::Url::parse("https://example.com/b").unwrap())
+            .is_none());
+    }
+
+    #[test]
+    fn validation_depth_is_bounded() {
+        let mut scope = Scope::<OwnedValue>::new();
+        scope.max_depth(10);
+        let schema = scope
+            .compile_and_return(json!({ "items": { "$ref": "#" } }), false)
+            .unwrap();
+
+        let mut value = json!([]);
+        for _ in 0..4 {
+            value = OwnedValue::Array(vec![value]);
+        }
+        assert!(schema.validate(&value).is_valid());
+
+        for _ in 0..4 {
+            value = OwnedValue::Array(vec![value]);
+        }
+        let state = schema.validate(&value);
+        assert!(!state.is_valid());
+        assert_eq!(state.errors[0].get_code(), "max_depth");
+    }
+
+    #[test]
+    fn default_validation_depth_doesnt_overflow() {
+        let mut scope = Scope::<OwnedValue>::new();
+        let schema = scope
+            .compile_and_return(json!({ "items": { "$ref": "#" } }), false)
+            .unwrap();
+
+        let mut value = json!([]);
+        for _ in 0..super::DEFAULT_MAX_DEPTH {
+            value = OwnedValue::Array(vec![value]);
+        }
+        assert!(!schema.validate(&value).is_valid());
+    }
+
+    #[test]
+    fn bundles_referenced_documents() {
+        let mut scope = Scope::<OwnedValue>::new();
+        scope.resolver(|url: &url::Url| match url.as_str() {
+            "https://example.com/item" => Some(json!({ "$ref": "defs#/definitions/positive" })),
+            "https://example.com/defs" => Some(json!({
+                "definitions": {
+                    "positive": { "type": "integer", "minimum": 1 },
+                    "name": { "type": "string" }
+                }
+            })),
+            _ => None,
+        });
+        scope
+            .compile_and_return(
+                json!({
+                    "$id": "https://example.com/root",
+                    "properties": {
+                        "a": { "$ref": "item" },
+                        "b": { "$ref": "https://example.com/defs#/definitions/name" },
+                        "c": { "$ref": "#/definitions/local" }
+                    },
+                    "definitions": { "local": { "const": "$ref" } }
+                }),
+                false,
+            )
+            .unwrap();
+
+        let bundle = scope
+            .bundle(&url::Url::parse("https://example.com/root").unwrap())
+            .unwrap();
+        assert_eq!(
+            bundle["properties"]["a"],
+            json!({ "$ref": "https://example.com/item" })
+        );
+        assert_eq!(
+            bundle["properties"]["c"],
+            json!({ "$ref": "#/definitions/local" })
+        );
+        assert_eq!(
+            bundle["definitions"]["https://example.com/item"]["$id"],
+            json!("https://example.com/item")
+        );
+        assert_eq!(
+            bundle["definitions"]["https://example.com/defs"]["$id"],
+            json!("https://example.com/defs")
+        );
+
+        let mut scope = Scope::<OwnedValue>::new();
+        scope.ban_unresolved_refs(true);
+        let schema = scope.compile_and_return(bundle, false).unwrap();
+        assert!(schema
+            .validate(&json!({ "a": 2, "b": "x", "c": "$ref" }))
+            .is_valid());
+        assert!(!schema.validate(&json!({ "a": 0 })).is_valid());
+        assert!(!schema.validate(&json!({ "b": 1 })).is_valid());
+    }
+
+    #[test]
+    fn bundles_under_defs_since_draft2019_09() {
+        let mut scope = Scope::<OwnedValue>::new();
+        scope
+            .compile_and_return(
+                json!({ "$id": "https://example.com/name", "type": "string" }),
+                false,
+            )
+            .unwrap();
+        scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "$id": "https://example.com/names",
+                    "items": { "$ref": "name" }
+                }),
+                false,
+            )
+            .unwrap();
+
+        let bundle = scope
+            .bundle(&url::Url::parse("https://example.com/names").unwrap())
+            .unwrap();
+        assert_eq!(
+            bundle["$defs"]["https://example.com/name"],
+            json!({ "$id": "https://example.com/name", "type": "string" })
+        );
+
+        assert!(scope
+            .bundle(&url::Url::parse("https://example.com/other").unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn dereferences_refs() {
+        let mut scope = Scope::<OwnedValue>::new();
+        scope
+            .compile_and_return(
+                json!({
+                    "$id": "https://example.com/person",
+                    "properties": {
+                        "name": { "$ref": "#/definitions/name" },
+                        "friend": { "$ref": "#" }
+                    },
+                    "definitions": { "name": { "type": "string" } }
+                }),
+                false,
+            )
+            .unwrap();
+
+        let dereferenced = scope
+            .dereference(&url::Url::parse("https://example.com/person").unwrap())
+            .unwrap();
+        assert_eq!(
+            dereferenced.schema["properties"],
+            json!({ "name": { "type": "string" }, "friend": { "$ref": "#" } })
+        );
+        assert_eq!(dereferenced.recursive, vec!["/properties/friend"]);
+
+        let dereferenced = scope
+            .dereference(&url::Url::parse("https://example.com/person#/properties/name").unwrap())
+            .unwrap();
+        assert_eq!(dereferenced.schema, json!({ "type": "string" }));
+    }
+
+    #[test]
+    fn dereferences_recursive_refs_through_ids() {
+        let mut scope = Scope::<OwnedValue>::new();
+        scope
+            .compile_and_return(
+                json!({
+                    "$id": "https://example.com/list",
+                    "definitions": {
+                        "node": {
+                            "$id": "node",
+                            "properties": { "next": { "$ref": "list#/definitions/node" } }
+                        }
+                    }
+                }),
+                false,
+            )
+            .unwrap();
+
+        let dereferenced = scope
+            .dereference(&url::Url::parse("https://example.com/node").unwrap())
+            .unwrap();
+        assert_eq!(
+            dereferenced.schema["properties"],
+            json!({ "next": { "$ref": "list#/definitions/node" } })
+        );
+        assert_eq!(dereferenced.recursive, vec!["/properties/next"]);
+    }
+
+    #[test]
+    fn dereferences_refs_to_other_documents() {
+        let mut scope = Scope::<OwnedValue>::new();
+        scope
+            .compile_and_return(
+                json!({
+                    "$id": "https://example.com/node",
+                    "type": "object",
+                    "properties": { "children": { "$ref": "tree" } }
+                }),
+                false,
+            )
+            .unwrap();
+        scope
+            .compile_and_return(
+                json!({ "$id": "https://example.com/tree", "items": { "$ref": "node" } }),
+                false,
+            )
+            .unwrap();
+
+        let dereferenced = scope
+            .dereference(&url::Url::parse("https://example.com/tree").unwrap())
+            .unwrap();
+        assert_eq!(
+            dereferenced.schema,
+            json!({
+                "$id": "https://example.com/tree",
+                "items": {
+                    "type": "object",
+                    "properties": { "children": { "$ref": "https://example.com/tree" } }
+                }
+            })
+        );
+        assert_eq!(dereferenced.recursive, vec!["/items/properties/children"]);
+    }
+
+    #[test]
+    fn dereferenced_refs_with_siblings_join_all_of() {
+        let mut scope = Scope::<OwnedValue>::new();
+        scope
+            .compile_and_return(
+                json!({ "$id": "https://example.com/base", "required": ["a"] }),
+                false,
+            )
+            .unwrap();
+        scope
+            .compile_and_return(
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "$id": "https://example.com/strict",
+                    "$ref": "base",
+                    "unevaluatedProperties": false
+                }),
+                false,
+            )
+            .unwrap();
+
+        let dereferenced = scope
+            .dereference(&url::Url::parse("https://example.com/strict").unwrap())
+            .unwrap();
+        assert_eq!(
+            dereferenced.schema,
+            json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "$id": "https://example.com/strict",
+                "unevaluatedProperties": false,
+                "allOf": [{ "required": ["a"] }]
+            })
+        );
+        assert!(dereferenced.recursive.is_empty());
     }
 }
@@ -30,6 +30,7 @@ where
     fn validate(&self, val: &V, path: &str, _scope: &scope::Scope<V>) -> super::ValidationState {
         let mut state = super::ValidationState::new();
 
+        #[allow(clippy::cmp_owned)]
         if val.to_string() != self.item.to_string() {
             state.errors.push(Box::new(error::Const {
                 path: path.to_string(),
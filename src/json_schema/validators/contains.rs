@@ -1,12 +1,12 @@
-use url;
 use value_trait::*;
 
 use super::error;
+use super::schema;
 use super::scope;
 
 #[allow(missing_copy_implementations)]
 pub struct Contains {
-    pub url: url::Url,
+    pub url: schema::Link,
 }
 
 impl<V> super::Validator<V> for Contains
@@ -30,23 +30,30 @@ where
     fn validate(&self, val: &V, path: &str, scope: &scope::Scope<V>) -> super::ValidationState {
         let array = nonstrict_process!(val.as_array(), path);
 
-        let schema = scope.resolve(&self.url);
+        let schema = scope.follow(&self.url);
         let mut state = super::ValidationState::new();
 
-        if schema.is_some() {
-            let schema = schema.unwrap();
-            let any_matched = array.iter().enumerate().any(|(idx, item)| {
+        if let Some(schema) = schema {
+            let tracks_evaluated = scope.tracks_evaluated();
+            let mut contained = false;
+            for (idx, item) in array.iter().enumerate() {
                 let item_path = [path, idx.to_string().as_ref()].join("/");
-                schema.validate_in(item, item_path.as_ref()).is_valid()
-            });
+                if schema.validate_in(item, item_path.as_ref()).is_valid() {
+                    contained = true;
+                    if !tracks_evaluated {
+                        break;
+                    }
+                    state.evaluated.insert(idx.to_string());
+                }
+            }
 
-            if !any_matched {
+            if !contained {
                 state.errors.push(Box::new(error::Contains {
                     path: path.to_string(),
                 }))
             }
         } else {
-            state.missing.push(self.url.clone());
+            state.missing.push(self.url.url().clone());
         }
 
         state
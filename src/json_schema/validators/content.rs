@@ -0,0 +1,224 @@
+use value_trait::*;
+
+use super::super::helpers;
+use super::error;
+use super::schema;
+use super::scope;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Base64,
+    Base64Url,
+    QuotedPrintable,
+}
+
+impl Encoding {
+    pub fn from_name(encoding: &str) -> Option<Encoding> {
+        match encoding.to_ascii_lowercase().as_ref() {
+            "base64" => Some(Encoding::Base64),
+            "base64url" => Some(Encoding::Base64Url),
+            "quoted-printable" => Some(Encoding::QuotedPrintable),
+            _ => None,
+        }
+    }
+
+    fn decode(self, string: &str) -> Option<Vec<u8>> {
+        match self {
+            Encoding::Base64 => decode_base64(string, b'+', b'/'),
+            Encoding::Base64Url => decode_base64(string, b'-', b'_'),
+            Encoding::QuotedPrintable => decode_quoted_printable(string),
+        }
+    }
+}
+
+fn base64_value(byte: u8, plus: u8, slash: u8) -> Option<u8> {
+    match byte {
+        b'A'..=b'Z' => Some(byte - b'A'),
+        b'a'..=b'z' => Some(byte - b'a' + 26),
+        b'0'..=b'9' => Some(byte - b'0' + 52),
+        _ if byte == plus => Some(62),
+        _ if byte == slash => Some(63),
+        _ => None,
+    }
+}
+
+// RFC 4648, with optional padding and the line breaks MIME wraps base64 with
+fn decode_base64(string: &str, plus: u8, slash: u8) -> Option<Vec<u8>> {
+    let input = string
+        .bytes()
+        .filter(|byte| *byte != b'\r' && *byte != b'\n')
+        .collect::<Vec<u8>>();
+
+    let padding = input.iter().rev().take_while(|byte| **byte == b'=').count();
+    if padding > 2 || (padding > 0 && input.len() % 4 != 0) {
+        return None;
+    }
+
+    let data = &input[..input.len() - padding];
+    if data.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(data.len() * 3 / 4);
+    for chunk in data.chunks(4) {
+        let mut buffer = 0u32;
+        for byte in chunk.iter() {
+            buffer = (buffer << 6) | u32::from(base64_value(*byte, plus, slash)?);
+        }
+        buffer <<= 6 * (4 - chunk.len() as u32);
+
+        bytes.push((buffer >> 16) as u8);
+        if chunk.len() > 2 {
+            bytes.push((buffer >> 8) as u8);
+        }
+        if chunk.len() > 3 {
+            bytes.push(buffer as u8);
+        }
+    }
+
+    Some(bytes)
+}
+
+// RFC 2045 section 6.7
+fn decode_quoted_printable(string: &str) -> Option<Vec<u8>> {
+    let input = string.as_bytes();
+    let mut bytes = Vec::with_capacity(input.len());
+
+    let mut idx = 0;
+    while idx < input.len() {
+        if input[idx] != b'=' {
+            bytes.push(input[idx]);
+            idx += 1;
+        } else if input[idx + 1..].starts_with(b"\r\n") {
+            idx += 3;
+        } else if input[idx + 1..].starts_with(b"\n") {
+            idx += 2;
+        } else if idx + 2 < input.len() && input[idx + 1..idx + 3].iter().all(u8::is_ascii_hexdigit)
+        {
+            let hex = std::str::from_utf8(&input[idx + 1..idx + 3]).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            idx += 3;
+        } else {
+            return None;
+        }
+    }
+
+    Some(bytes)
+}
+
+#[allow(missing_copy_implementations)]
+pub struct Content {
+    pub encoding: Option<Encoding>,
+    // Whether `contentMediaType` is `application/json`, the only media type that is checked
+    pub json: bool,
+    pub schema: Option<schema::Link>,
+}
+
+impl<V> super::Validator<V> for Content
+where
+    V: Value
+        + std::clone::Clone
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::fmt::Display
+        + std::marker::Sync
+        + std::marker::Send
+        + std::cmp::PartialEq,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::hash::Hash
+        + Eq
+        + std::convert::AsRef<str>
+        + std::fmt::Debug
+        + std::string::ToString
+        + std::marker::Sync
+        + std::marker::Send,
+{
+    fn validate(&self, val: &V, path: &str, scope: &scope::Scope<V>) -> super::ValidationState {
+        let string = nonstrict_process!(val.as_str(), path);
+
+        let bytes = if let Some(encoding) = self.encoding {
+            match encoding.decode(string) {
+                Some(bytes) => bytes,
+                None => {
+                    return val_error!(error::Content {
+                        path: path.to_string(),
+                        detail: "The content is not encoded as contentEncoding states".to_string()
+                    })
+                }
+            }
+        } else {
+            string.as_bytes().to_vec()
+        };
+
+        if !self.json {
+            return super::ValidationState::new();
+        }
+
+        let document = match helpers::parse_json(&bytes) {
+            Some(document) => document,
+            None => {
+                return val_error!(error::Content {
+                    path: path.to_string(),
+                    detail: "The content is not a valid JSON document".to_string()
+                })
+            }
+        };
+
+        let mut state = super::ValidationState::new();
+
+        if let Some(ref url) = self.schema {
+            if let Some(schema) = scope.follow(url) {
+                // The embedded document is a separate instance, so nothing in it counts as
+                // evaluated for the enclosing one
+                let content_state = schema.validate_in(&V::from(document), path);
+                if !content_state.is_valid() {
+                    state.errors.push(Box::new(error::Content {
+                        path: path.to_string(),
+                        detail: "The content does not match contentSchema".to_string(),
+                    }));
+                }
+                state.errors.extend(content_state.errors);
+                state.missing.extend(content_state.missing);
+            } else {
+                state.missing.push(url.url().clone())
+            }
+        }
+
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Encoding;
+
+    #[test]
+    fn base64() {
+        let decode = |string| Encoding::Base64.decode(string);
+        assert_eq!(decode("eyJhIjoxfQ=="), Some(b"{\"a\":1}".to_vec()));
+        assert_eq!(decode("eyJhIjoxfQ"), Some(b"{\"a\":1}".to_vec()));
+        assert_eq!(decode("Pz8/\r\nPz8+"), Some(b"?????>".to_vec()));
+        assert_eq!(decode(""), Some(vec![]));
+        assert_eq!(decode("eyJhIjoxfQ="), None);
+        assert_eq!(decode("e"), None);
+        assert_eq!(decode("Pz8_"), None);
+    }
+
+    #[test]
+    fn base64url() {
+        let decode = |string| Encoding::Base64Url.decode(string);
+        assert_eq!(decode("Pz8_Pz8-"), Some(b"?????>".to_vec()));
+        assert_eq!(decode("Pz8/"), None);
+    }
+
+    #[test]
+    fn quoted_printable() {
+        let decode = |string| Encoding::QuotedPrintable.decode(string);
+        assert_eq!(decode("a=3Db"), Some(b"a=b".to_vec()));
+        assert_eq!(
+            decode("soft=\r\nbreak=\nhere"),
+            Some(b"softbreakhere".to_vec())
+        );
+        assert_eq!(decode("a=3"), None);
+        assert_eq!(decode("a=ZZ"), None);
+    }
+}
@@ -1,12 +1,12 @@
-use url;
 use value_trait::*;
 
 use super::error;
+use super::schema;
 use super::scope;
 
 #[derive(Debug)]
 pub enum DepKind {
-    Schema(url::Url),
+    Schema(schema::Link),
     Property(Vec<String>),
 }
 
@@ -41,19 +41,18 @@ where
         let mut state = super::ValidationState::new();
 
         for (key, dep) in self.items.iter() {
-            if object.get(&key).is_some() {
+            if object.get(key).is_some() {
                 match dep {
                     DepKind::Schema(ref url) => {
-                        let schema = scope.resolve(url);
-                        if schema.is_some() {
-                            state.append(schema.unwrap().validate_in(object, path));
+                        if let Some(schema) = scope.follow(url) {
+                            state.append(schema.validate_in(object, path));
                         } else {
-                            state.missing.push(url.clone())
+                            state.missing.push(url.url().clone())
                         }
                     }
                     DepKind::Property(ref keys) => {
                         for key in keys.iter() {
-                            if object.get(&key).is_none() {
+                            if object.get(key).is_none() {
                                 state.errors.push(Box::new(error::Required {
                                     path: [path, key.as_ref()].join("/"),
                                 }))
@@ -66,4 +65,14 @@ where
 
         state
     }
+
+    fn in_place(&self) -> Vec<&schema::Link> {
+        self.items
+            .values()
+            .filter_map(|item| match item {
+                DepKind::Schema(ref link) => Some(link),
+                DepKind::Property(_) => None,
+            })
+            .collect()
+    }
 }
@@ -0,0 +1,24 @@
+use url;
+use value_trait::*;
+
+use super::error;
+use super::scope;
+
+#[allow(missing_copy_implementations)]
+pub struct Deprecated {
+    pub schema: url::Url,
+}
+
+impl<V> super::Validator<V> for Deprecated
+where
+    V: Value,
+{
+    fn validate(&self, _val: &V, path: &str, _scope: &scope::Scope<V>) -> super::ValidationState {
+        let mut state = super::ValidationState::new();
+        state.warnings.push(Box::new(error::Deprecated {
+            path: path.to_string(),
+            schema: self.schema.clone(),
+        }));
+        state
+    }
+}
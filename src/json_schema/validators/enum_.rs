@@ -34,35 +34,26 @@ where
         // FIXME: Ugly, dangerous and likely bad for null
         for value in self.items.as_array().unwrap() {
             if val.as_bool().is_some() && value.as_bool().is_some() {
-                dbg!(val.as_bool().unwrap());
-                dbg!(value.as_bool().unwrap());
-
                 if val.as_bool().unwrap() == value.as_bool().unwrap() {
                     contains = true;
                     break;
                 }
             } else if value.as_f64().is_some() && val.as_f64().is_some() {
-                dbg!(value);
-
                 if val.as_f64().unwrap() == value.as_f64().unwrap() {
                     contains = true;
                     break;
                 }
             } else if value.as_i64().is_some() && val.as_i64().is_some() {
-                dbg!(value);
-
                 if val.as_i64().unwrap() == value.as_i64().unwrap() {
                     contains = true;
                     break;
                 }
-            } else if value.as_str().is_some() && val.as_str().is_some() {
-                dbg!(val.as_str().unwrap());
-                dbg!(value.as_str().unwrap());
-
-                if val.as_str().unwrap() == value.as_str().unwrap() {
-                    contains = true;
-                    break;
-                }
+            } else if value.as_str().is_some()
+                && val.as_str().is_some()
+                && val.as_str().unwrap() == value.as_str().unwrap()
+            {
+                contains = true;
+                break;
             }
         }
 
@@ -4,8 +4,27 @@ use publicsuffix::List;
 use std::net;
 use value_trait::*;
 
+use super::super::regex_engine;
 use super::error;
 
+/// Reports the failures of a format validator as warnings, for `FormatMode::Annotate`.
+pub struct Annotated<V> {
+    pub validator: super::BoxedValidator<V>,
+}
+
+impl<V> super::Validator<V> for Annotated<V>
+where
+    V: Value,
+    <V as Value>::Key: std::borrow::Borrow<str> + std::hash::Hash + Eq + std::convert::AsRef<str>,
+{
+    fn validate(&self, val: &V, path: &str, scope: &scope::Scope<V>) -> super::ValidationState {
+        let mut state = self.validator.validate(val, path, scope);
+        let errors = std::mem::take(&mut state.errors);
+        state.warnings.extend(errors);
+        state
+    }
+}
+
 #[allow(missing_copy_implementations)]
 pub struct DateTime;
 
@@ -44,6 +63,14 @@ where
     fn validate(&self, val: &V, path: &str, _scope: &scope::Scope<V>) -> super::ValidationState {
         let string = nonstrict_process!(val.as_str(), path);
 
+        // Non-ASCII addresses are `idn-email`
+        if !string.is_ascii() {
+            return val_error!(error::Format {
+                path: path.to_string(),
+                detail: "Malformed email address".to_string()
+            });
+        }
+
         match List::empty().parse_email(string) {
             Ok(_) => super::ValidationState::new(),
             Err(_) => val_error!(error::Format {
@@ -64,6 +91,14 @@ where
     fn validate(&self, val: &V, path: &str, _scope: &scope::Scope<V>) -> super::ValidationState {
         let string = nonstrict_process!(val.as_str(), path);
 
+        // Non-ASCII hostnames are `idn-hostname`
+        if !string.is_ascii() {
+            return val_error!(error::Format {
+                path: path.to_string(),
+                detail: "Malformed hostname".to_string()
+            });
+        }
+
         match List::empty().parse_domain(string) {
             Ok(_) => super::ValidationState::new(),
             Err(_) => val_error!(error::Format {
@@ -175,3 +210,404 @@ where
         }
     }
 }
+
+macro_rules! format_validator {
+    ($name:ident, $check:ident) => {
+        #[allow(missing_copy_implementations)]
+        pub struct $name;
+
+        impl<V> super::Validator<V> for $name
+        where
+            V: Value,
+        {
+            fn validate(
+                &self,
+                val: &V,
+                path: &str,
+                _scope: &scope::Scope<V>,
+            ) -> super::ValidationState {
+                let string = nonstrict_process!(val.as_str(), path);
+
+                match $check(string) {
+                    Ok(_) => super::ValidationState::new(),
+                    Err(detail) => val_error!(error::Format {
+                        path: path.to_string(),
+                        detail
+                    }),
+                }
+            }
+        }
+    };
+}
+
+format_validator!(Date, check_date);
+format_validator!(Time, check_time);
+format_validator!(Duration, check_duration);
+format_validator!(IdnEmail, check_idn_email);
+format_validator!(IdnHostname, check_idn_hostname);
+format_validator!(Iri, check_iri);
+format_validator!(IriReference, check_iri_reference);
+format_validator!(UriTemplate, check_uri_template);
+format_validator!(JsonPointer, check_json_pointer);
+format_validator!(RelativeJsonPointer, check_relative_json_pointer);
+format_validator!(Regex, check_regex);
+
+// Parses a fixed number of ASCII digits
+fn digits(string: &str, len: usize) -> Option<u32> {
+    if string.len() == len && string.bytes().all(|byte| byte.is_ascii_digit()) {
+        string.parse().ok()
+    } else {
+        None
+    }
+}
+
+// RFC 3339 full-date
+fn check_date(string: &str) -> Result<(), String> {
+    if !string.is_ascii() || string.len() != 10 || &string[4..5] != "-" || &string[7..8] != "-" {
+        return Err("Malformed date, expected YYYY-MM-DD".to_string());
+    }
+
+    let year = digits(&string[0..4], 4);
+    let month = digits(&string[5..7], 2);
+    let day = digits(&string[8..10], 2);
+
+    if year.is_none() || month.is_none() || day.is_none() {
+        return Err("Malformed date, expected YYYY-MM-DD".to_string());
+    }
+
+    match chrono::NaiveDate::from_ymd_opt(year.unwrap() as i32, month.unwrap(), day.unwrap()) {
+        Some(_) => Ok(()),
+        None => Err(format!("{} is not a day of the calendar", string)),
+    }
+}
+
+// RFC 3339 full-time
+fn check_time(string: &str) -> Result<(), String> {
+    let malformed = || "Malformed time, expected HH:MM:SS[.frac](Z|+HH:MM|-HH:MM)".to_string();
+
+    if !string.is_ascii() || string.len() < 9 || &string[2..3] != ":" || &string[5..6] != ":" {
+        return Err(malformed());
+    }
+
+    let hour = digits(&string[0..2], 2).ok_or_else(malformed)?;
+    let minute = digits(&string[3..5], 2).ok_or_else(malformed)?;
+    let second = digits(&string[6..8], 2).ok_or_else(malformed)?;
+
+    let mut rest = &string[8..];
+    if rest.starts_with('.') {
+        let fraction = rest[1..]
+            .bytes()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count();
+        if fraction == 0 {
+            return Err(malformed());
+        }
+        rest = &rest[1 + fraction..];
+    }
+
+    let offset = if rest.eq_ignore_ascii_case("z") {
+        0
+    } else if rest.len() == 6
+        && (rest.starts_with('+') || rest.starts_with('-'))
+        && &rest[3..4] == ":"
+    {
+        let offset_hour = digits(&rest[1..3], 2).ok_or_else(malformed)?;
+        let offset_minute = digits(&rest[4..6], 2).ok_or_else(malformed)?;
+        if offset_hour > 23 || offset_minute > 59 {
+            return Err(format!("{} is not a valid time offset", rest));
+        }
+        let offset = (offset_hour * 60 + offset_minute) as i32;
+        if rest.starts_with('+') {
+            offset
+        } else {
+            -offset
+        }
+    } else {
+        return Err(malformed());
+    };
+
+    if hour > 23 || minute > 59 || second > 60 {
+        return Err(format!("{} is not a valid time of day", &string[..8]));
+    }
+
+    // A leap second can only be inserted at the end of a UTC day
+    if second == 60 {
+        let utc_minute = ((hour * 60 + minute) as i32 - offset).rem_euclid(24 * 60);
+        if utc_minute != 23 * 60 + 59 {
+            return Err("A leap second must be at 23:59:60 UTC".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+// Consumes consecutive `<digits><unit>` components of an ISO 8601 duration. Following RFC 3339
+// appendix A, units can't be skipped, e.g. `P1Y1D` needs a month in between.
+fn duration_components(part: &str, units: &[char]) -> Result<(), String> {
+    let mut next_unit: Option<usize> = None;
+    let mut rest = part;
+
+    while !rest.is_empty() {
+        let number = rest
+            .bytes()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count();
+        if number == 0 {
+            return Err(format!("Expected a number in duration component {}", rest));
+        }
+
+        let unit = rest[number..].chars().next();
+        let position = unit.and_then(|unit| units.iter().position(|u| *u == unit));
+        match (position, next_unit) {
+            (Some(position), None) => next_unit = Some(position + 1),
+            (Some(position), Some(next)) if position == next => next_unit = Some(position + 1),
+            _ => {
+                return Err(format!(
+                    "Duration units must follow each other in the order {:?}",
+                    units
+                ))
+            }
+        }
+
+        rest = &rest[number + 1..];
+    }
+
+    if next_unit.is_none() {
+        return Err("A duration part must have at least one component".to_string());
+    }
+
+    Ok(())
+}
+
+// RFC 3339 appendix A duration
+fn check_duration(string: &str) -> Result<(), String> {
+    if !string.is_ascii() || !string.starts_with('P') {
+        return Err("A duration must start with P".to_string());
+    }
+
+    let rest = &string[1..];
+    if let Some(weeks) = rest.strip_suffix('W') {
+        return if !weeks.is_empty() && weeks.bytes().all(|byte| byte.is_ascii_digit()) {
+            Ok(())
+        } else {
+            Err("A duration in weeks can't be combined with other units".to_string())
+        };
+    }
+
+    let mut parts = rest.splitn(2, 'T');
+    let date = parts.next().unwrap();
+    let time = parts.next();
+
+    if !date.is_empty() || time.is_none() {
+        duration_components(date, &['Y', 'M', 'D'])?;
+    }
+    if let Some(time) = time {
+        duration_components(time, &['H', 'M', 'S'])?;
+    }
+
+    Ok(())
+}
+
+// RFC 6531, which allows UTF-8 in both the local part and the domain
+fn check_idn_email(string: &str) -> Result<(), String> {
+    match List::empty().parse_email(string) {
+        Ok(_) => Ok(()),
+        Err(_) => Err("Malformed internationalized email address".to_string()),
+    }
+}
+
+// RFC 5890, checked by converting the labels to A-labels
+fn check_idn_hostname(string: &str) -> Result<(), String> {
+    // Hyphens are checked on U-labels, the A-label of `-bücher` is `xn---bcher-kva`
+    if string
+        .split('.')
+        .any(|label| label.starts_with('-') || label.ends_with('-'))
+    {
+        return Err("A hostname label can't start or end with a hyphen".to_string());
+    }
+
+    match List::empty().parse_domain(string) {
+        Ok(_) => Ok(()),
+        Err(_) => Err("Malformed internationalized hostname".to_string()),
+    }
+}
+
+// Characters RFC 3987 excludes from IRIs, which URL parsers would percent-encode instead
+fn check_iri_chars(string: &str) -> Result<(), String> {
+    match string
+        .chars()
+        .find(|c| c.is_control() || c.is_whitespace() || "\"<>\\^`{|}".contains(*c))
+    {
+        Some(c) => Err(format!("{:?} is not allowed in an IRI", c)),
+        None => Ok(()),
+    }
+}
+
+// RFC 3987
+fn check_iri(string: &str) -> Result<(), String> {
+    check_iri_chars(string)?;
+
+    match url::Url::parse(string) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("Malformed IRI: {}", err)),
+    }
+}
+
+// RFC 3987
+fn check_iri_reference(string: &str) -> Result<(), String> {
+    check_iri_chars(string)?;
+
+    let base_url = url::Url::parse("http://example.com/").unwrap();
+
+    match base_url.join(string) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("Malformed IRI reference: {}", err)),
+    }
+}
+
+// Checks the `%XX` escape starting at `idx`
+fn pct_encoded(bytes: &[u8], idx: usize) -> bool {
+    bytes.len() > idx + 2
+        && bytes[idx + 1].is_ascii_hexdigit()
+        && bytes[idx + 2].is_ascii_hexdigit()
+}
+
+// RFC 6570 varspec: varname [ ":" max-length / "*" ]
+fn check_varspec(varspec: &str) -> Result<(), String> {
+    let (name, modifier) = match varspec.find([':', '*']) {
+        Some(idx) => (&varspec[..idx], Some(&varspec[idx..])),
+        None => (varspec, None),
+    };
+
+    let bytes = name.as_bytes();
+    let mut idx = 0;
+    let mut previous_dot = true;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'%' if pct_encoded(bytes, idx) => idx += 3,
+            b'.' if !previous_dot => {
+                previous_dot = true;
+                idx += 1;
+                continue;
+            }
+            byte if byte.is_ascii_alphanumeric() || byte == b'_' => idx += 1,
+            _ => return Err(format!("{:?} is not a valid variable name", name)),
+        }
+        previous_dot = false;
+    }
+    if previous_dot {
+        return Err(format!("{:?} is not a valid variable name", name));
+    }
+
+    match modifier {
+        None | Some("*") => Ok(()),
+        Some(modifier) => {
+            let length = &modifier[1..];
+            if modifier.starts_with(':')
+                && !length.is_empty()
+                && length.len() <= 4
+                && !length.starts_with('0')
+                && length.bytes().all(|byte| byte.is_ascii_digit())
+            {
+                Ok(())
+            } else {
+                Err(format!("{:?} is not a valid prefix modifier", modifier))
+            }
+        }
+    }
+}
+
+// RFC 6570 level 4
+fn check_uri_template(string: &str) -> Result<(), String> {
+    let bytes = string.as_bytes();
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'{' => {
+                let end = match string[idx..].find('}') {
+                    Some(end) => idx + end,
+                    None => return Err("Unclosed expression in URI template".to_string()),
+                };
+
+                let mut expression = &string[idx + 1..end];
+                if expression.starts_with(|c| "+#./;?&".contains(c)) {
+                    expression = &expression[1..];
+                } else if expression.starts_with(|c| "=,!@|".contains(c)) {
+                    return Err(format!(
+                        "Operator {:?} is reserved in URI templates",
+                        &expression[..1]
+                    ));
+                }
+
+                for varspec in expression.split(',') {
+                    check_varspec(varspec)?;
+                }
+
+                idx = end + 1;
+            }
+            b'%' => {
+                if !pct_encoded(bytes, idx) {
+                    return Err("Malformed percent-encoding in URI template".to_string());
+                }
+                idx += 3;
+            }
+            byte if byte <= b' ' || byte == 0x7f || b"\"'<>\\^`|}".contains(&byte) => {
+                return Err(format!(
+                    "{:?} is not allowed in a URI template literal",
+                    byte as char
+                ))
+            }
+            _ => idx += 1,
+        }
+    }
+
+    Ok(())
+}
+
+// RFC 6901
+fn check_json_pointer(string: &str) -> Result<(), String> {
+    if !string.is_empty() && !string.starts_with('/') {
+        return Err("A JSON pointer must be empty or start with /".to_string());
+    }
+
+    let mut chars = string.chars();
+    while let Some(c) = chars.next() {
+        if c == '~' && !matches!(chars.next(), Some('0') | Some('1')) {
+            return Err("~ must be escaped as ~0 in a JSON pointer".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+// draft-handrews-relative-json-pointer-01
+fn check_relative_json_pointer(string: &str) -> Result<(), String> {
+    let prefix = string
+        .bytes()
+        .take_while(|byte| byte.is_ascii_digit())
+        .count();
+    if prefix == 0 || (prefix > 1 && string.starts_with('0')) {
+        return Err("A relative JSON pointer must start with a non-negative integer".to_string());
+    }
+
+    match &string[prefix..] {
+        "#" => Ok(()),
+        pointer => check_json_pointer(pointer),
+    }
+}
+
+// The format is ECMA-262 syntax whichever engine the schema's own patterns use, and constructs the
+// `regex` crate can't run are still valid
+fn check_regex(string: &str) -> Result<(), String> {
+    let ast = regex_engine::ecma::parse(string)
+        .map_err(|err| format!("Malformed regular expression: {}", err))?;
+
+    match regex_engine::ecma::translate(&ast.node) {
+        Ok(translated) => match regex::Regex::new(&translated) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(format!("Malformed regular expression: {}", err)),
+        },
+        Err(_) => Ok(()),
+    }
+}
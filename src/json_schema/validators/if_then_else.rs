@@ -0,0 +1,70 @@
+use value_trait::*;
+
+use super::schema;
+use super::scope;
+
+#[allow(missing_copy_implementations)]
+pub struct IfThenElse {
+    pub if_: schema::Link,
+    pub then: Option<schema::Link>,
+    pub else_: Option<schema::Link>,
+}
+
+impl<V> super::Validator<V> for IfThenElse
+where
+    V: Value
+        + std::clone::Clone
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::fmt::Display
+        + std::marker::Sync
+        + std::marker::Send
+        + std::cmp::PartialEq,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::hash::Hash
+        + Eq
+        + std::convert::AsRef<str>
+        + std::fmt::Debug
+        + std::string::ToString
+        + std::marker::Sync
+        + std::marker::Send,
+{
+    fn validate(&self, val: &V, path: &str, scope: &scope::Scope<V>) -> super::ValidationState {
+        let mut state = super::ValidationState::new();
+
+        let schema = scope.follow(&self.if_);
+        if schema.is_none() {
+            state.missing.push(self.if_.url().clone());
+            return state;
+        }
+
+        let if_state = schema.unwrap().validate_in(val, path);
+        state.missing.extend(if_state.missing.clone());
+
+        // Errors of `if` only pick the branch, but its annotations and warnings count when it
+        // passes
+        let branch = if if_state.is_valid() {
+            state.evaluated.extend(if_state.evaluated);
+            state.warnings.extend(if_state.warnings);
+            &self.then
+        } else {
+            &self.else_
+        };
+
+        if let Some(url) = branch {
+            if let Some(schema) = scope.follow(url) {
+                state.append(schema.validate_in(val, path));
+            } else {
+                state.missing.push(url.url().clone())
+            }
+        }
+
+        state
+    }
+
+    fn in_place(&self) -> Vec<&schema::Link> {
+        let mut links = vec![&self.if_];
+        links.extend(self.then.iter());
+        links.extend(self.else_.iter());
+        links
+    }
+}
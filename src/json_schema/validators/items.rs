@@ -1,20 +1,20 @@
 use std::cmp;
-use url;
 use value_trait::*;
 
 use super::error;
+use super::schema;
 use super::scope;
 
 #[derive(Debug)]
 pub enum ItemsKind {
-    Schema(url::Url),
-    Array(Vec<url::Url>),
+    Schema(schema::Link),
+    Array(Vec<schema::Link>),
 }
 
 #[derive(Debug)]
 pub enum AdditionalKind {
     Boolean(bool),
-    Schema(url::Url),
+    Schema(schema::Link),
 }
 
 #[allow(missing_copy_implementations)]
@@ -45,33 +45,37 @@ where
         let array = nonstrict_process!(val.as_array(), path);
 
         let mut state = super::ValidationState::new();
+        let tracks_evaluated = scope.tracks_evaluated();
 
         match self.items {
             Some(ItemsKind::Schema(ref url)) => {
-                let schema = scope.resolve(url);
-                if schema.is_some() {
-                    let schema = schema.unwrap();
+                if let Some(schema) = scope.follow(url) {
                     for (idx, item) in array.iter().enumerate() {
                         let item_path = [path, idx.to_string().as_ref()].join("/");
-                        state.append(schema.validate_in(item, item_path.as_ref()));
+                        state.append_nested(schema.validate_in(item, item_path.as_ref()));
+                        if tracks_evaluated {
+                            state.evaluated.insert(idx.to_string());
+                        }
                     }
                 } else {
-                    state.missing.push(url.clone());
+                    state.missing.push(url.url().clone());
                 }
             }
             Some(ItemsKind::Array(ref urls)) => {
                 let min = cmp::min(urls.len(), array.len());
 
                 // Validate against schemas
-                for idx in 0..min {
-                    let schema = scope.resolve(&urls[idx]);
+                for (idx, url) in urls.iter().enumerate().take(min) {
                     let item = &array.get(idx).unwrap();
 
-                    if schema.is_some() {
+                    if let Some(schema) = scope.follow(url) {
                         let item_path = [path, idx.to_string().as_ref()].join("/");
-                        state.append(schema.unwrap().validate_in(item, item_path.as_ref()))
+                        state.append_nested(schema.validate_in(item, item_path.as_ref()));
+                        if tracks_evaluated {
+                            state.evaluated.insert(idx.to_string());
+                        }
                     } else {
-                        state.missing.push(urls[idx].clone())
+                        state.missing.push(url.url().clone())
                     }
                 }
 
@@ -85,20 +89,29 @@ where
                             }))
                         }
                         Some(AdditionalKind::Schema(ref url)) => {
-                            let schema = scope.resolve(url);
-                            if schema.is_some() {
-                                let schema = schema.unwrap();
+                            if let Some(schema) = scope.follow(url) {
                                 for (idx, item) in
                                     array.get(urls.len()..).unwrap().iter().enumerate()
                                 {
-                                    let item_path = [path, idx.to_string().as_ref()].join("/");
-                                    state.append(schema.validate_in(item, item_path.as_ref()))
+                                    let item_path =
+                                        [path, (urls.len() + idx).to_string().as_ref()].join("/");
+                                    state.append_nested(
+                                        schema.validate_in(item, item_path.as_ref()),
+                                    );
+                                    if tracks_evaluated {
+                                        state.evaluated.insert((urls.len() + idx).to_string());
+                                    }
                                 }
                             } else {
-                                state.missing.push(url.clone())
+                                state.missing.push(url.url().clone())
                             }
                         }
-                        _ => (),
+                        Some(AdditionalKind::Boolean(_)) if tracks_evaluated => {
+                            for idx in urls.len()..array.len() {
+                                state.evaluated.insert(idx.to_string());
+                            }
+                        }
+                        Some(AdditionalKind::Boolean(_)) | None => (),
                     }
                 }
             }
@@ -107,4 +120,45 @@ where
 
         state
     }
+
+    fn normalize(
+        &self,
+        val: &mut V,
+        path: &str,
+        scope: &scope::Scope<V>,
+        normalization: &mut super::Normalization,
+    ) where
+        V: Mutable,
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::convert::From<String>,
+    {
+        let len = match val.as_array() {
+            Some(array) => array.len(),
+            None => return,
+        };
+
+        for idx in 0..len {
+            let url = match self.items {
+                Some(ItemsKind::Schema(ref url)) => Some(url),
+                Some(ItemsKind::Array(ref urls)) => match self.additional {
+                    _ if idx < urls.len() => urls.get(idx),
+                    Some(AdditionalKind::Schema(ref url)) => Some(url),
+                    _ => None,
+                },
+                None => None,
+            };
+
+            if let Some(schema) = url.and_then(|url| scope.follow(url)) {
+                let item_path = [path, idx.to_string().as_ref()].join("/");
+                schema.normalize_in(
+                    val.get_idx_mut(idx).unwrap(),
+                    item_path.as_ref(),
+                    normalization,
+                );
+            }
+        }
+    }
 }
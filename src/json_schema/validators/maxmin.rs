@@ -24,7 +24,7 @@ where
         + std::marker::Send,
 {
     fn validate(&self, val: &V, path: &str, _scope: &scope::Scope<V>) -> super::ValidationState {
-        let number = nonstrict_process!(val.as_f64(), path);
+        let number = nonstrict_process!(val.cast_f64(), path);
 
         if number <= self.number {
             super::ValidationState::new()
@@ -57,7 +57,7 @@ where
         + std::marker::Send,
 {
     fn validate(&self, val: &V, path: &str, _scope: &scope::Scope<V>) -> super::ValidationState {
-        let number = nonstrict_process!(val.as_f64(), path);
+        let number = nonstrict_process!(val.cast_f64(), path);
 
         if number < self.number {
             super::ValidationState::new()
@@ -90,7 +90,7 @@ where
         + std::marker::Send,
 {
     fn validate(&self, val: &V, path: &str, _scope: &scope::Scope<V>) -> super::ValidationState {
-        let number = nonstrict_process!(val.as_f64(), path);
+        let number = nonstrict_process!(val.cast_f64(), path);
 
         if number >= self.number {
             super::ValidationState::new()
@@ -123,7 +123,7 @@ where
         + std::marker::Send,
 {
     fn validate(&self, val: &V, path: &str, _scope: &scope::Scope<V>) -> super::ValidationState {
-        let number = nonstrict_process!(val.as_f64(), path);
+        let number = nonstrict_process!(val.cast_f64(), path);
 
         if number > self.number {
             super::ValidationState::new()
@@ -1,5 +1,7 @@
 use super::error;
+use super::helpers;
 use super::primitive_types;
+use super::schema;
 use super::scope;
 use std::fmt;
 use value_trait::*;
@@ -19,7 +21,7 @@ macro_rules! val_error {
     ($err:expr) => {
         $crate::json_schema::validators::ValidationState {
             errors: vec![Box::new($err)],
-            missing: vec![],
+            ..$crate::json_schema::validators::ValidationState::new()
         }
     };
 }
@@ -41,8 +43,11 @@ macro_rules! strict_process {
 
 pub use self::const_::Const;
 pub use self::contains::Contains;
+pub use self::content::Content;
 pub use self::dependencies::Dependencies;
+pub use self::deprecated::Deprecated;
 pub use self::enum_::Enum;
+pub use self::if_then_else::IfThenElse;
 pub use self::items::Items;
 pub use self::maxmin::{ExclusiveMaximum, ExclusiveMinimum, Maximum, Minimum};
 pub use self::maxmin_items::{MaxItems, MinItems};
@@ -54,17 +59,22 @@ pub use self::of::{AllOf, AnyOf, OneOf};
 pub use self::pattern::Pattern;
 pub use self::properties::Properties;
 pub use self::property_names::PropertyNames;
-pub use self::ref_::Ref;
+pub use self::read_write_only::ReadWriteOnly;
+pub use self::ref_::{DynamicRef, RecursiveRef, Ref};
 pub use self::required::Required;
 pub use self::type_::Type;
+pub use self::unevaluated::{UnevaluatedItems, UnevaluatedProperties};
 pub use self::unique_items::UniqueItems;
 
 pub mod const_;
 pub mod contains;
+pub mod content;
 pub mod dependencies;
+pub mod deprecated;
 pub mod enum_;
+pub mod formats;
+pub mod if_then_else;
 pub mod items;
-//pub mod formats;
 mod maxmin;
 mod maxmin_items;
 mod maxmin_length;
@@ -75,9 +85,11 @@ pub mod of;
 pub mod pattern;
 pub mod properties;
 pub mod property_names;
+pub mod read_write_only;
 pub mod ref_;
 pub mod required;
 pub mod type_;
+pub mod unevaluated;
 pub mod unique_items;
 
 pub trait Validator<V>
@@ -88,12 +100,122 @@ where
     where
         <V as Value>::Key:
             std::borrow::Borrow<str> + std::hash::Hash + Eq + std::convert::AsRef<str>;
+
+    /// Deferred validators run after all other validators of their schema, through
+    /// `validate_evaluated`, so they can see which parts of the instance those evaluated.
+    fn is_deferred(&self) -> bool {
+        false
+    }
+
+    fn validate_evaluated(
+        &self,
+        item: &V,
+        path: &str,
+        scope: &scope::Scope<V>,
+        _evaluated: &ValidationState,
+    ) -> ValidationState
+    where
+        <V as Value>::Key:
+            std::borrow::Borrow<str> + std::hash::Hash + Eq + std::convert::AsRef<str>,
+    {
+        self.validate(item, path, scope)
+    }
+
+    /// Rewrites `item` as `options` of `normalization` ask, before it is validated. Validators of
+    /// keywords with subschemas pass it on to them, `properties` inserts defaults.
+    fn normalize(
+        &self,
+        _item: &mut V,
+        _path: &str,
+        _scope: &scope::Scope<V>,
+        _normalization: &mut Normalization,
+    ) where
+        V: Mutable,
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::convert::From<String>,
+    {
+    }
+
+    /// The schemas the validator applies that aren't its own subschemas, e.g. the target of
+    /// `$ref`. `Scope` makes sure they are compiled.
+    fn references(&self) -> Vec<&url::Url> {
+        vec![]
+    }
+
+    /// The subschemas the validator applies to `item` itself rather than to values in it, e.g.
+    /// the target of `$ref`. `Scope` rejects schemas that reach themselves through these.
+    fn in_place(&self) -> Vec<&schema::Link> {
+        vec![]
+    }
+
+    /// Whether the validator resolves its subschema through the schema resources the validation
+    /// entered, e.g. `$dynamicRef`. Validations only track those in scopes with such validators.
+    fn is_dynamic(&self) -> bool {
+        false
+    }
+
+    /// Converts `item` to match the keyword where that is lossless, for
+    /// `NormalizeOptions::coerce_types`. Runs on a value before any `normalize` of its schema.
+    fn coerce(&self, _item: &mut V, _path: &str, _coerced: &mut Vec<Coercion>)
+    where
+        V: Mutable,
+    {
+    }
+}
+
+/// A value `coerce` converted from one type to another.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coercion {
+    pub path: String,
+    pub from: primitive_types::PrimitiveType,
+    pub to: primitive_types::PrimitiveType,
+}
+
+/// The changes a normalization made to an instance.
+#[derive(Debug)]
+pub struct Normalization {
+    pub options: schema::NormalizeOptions,
+    /// JSON pointers of the values inserted from `default`.
+    pub populated: Vec<String>,
+    pub coerced: Vec<Coercion>,
+    /// JSON pointers of the properties removed as additional.
+    pub removed: Vec<String>,
+    /// JSON pointers of the properties removed as `readOnly` or `writeOnly`.
+    pub stripped: Vec<String>,
+}
+
+impl Normalization {
+    pub fn new(options: schema::NormalizeOptions) -> Normalization {
+        Normalization {
+            options,
+            populated: vec![],
+            coerced: vec![],
+            removed: vec![],
+            stripped: vec![],
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct ValidationState {
     pub errors: super::error::SimdjsonSchemaErrors,
     pub missing: Vec<url::Url>,
+    /// Names of the properties and indexes of the items of the validated value that were
+    /// evaluated by a subschema, e.g. through `properties` or `items`. Only successfully validated
+    /// in-place applicators contribute, and only in scopes that track them, see
+    /// `Scope::tracks_evaluated`.
+    pub evaluated: hashbrown::HashSet<String>,
+    /// Failures that don't make the instance invalid, e.g. of an annotation-only `format`.
+    pub warnings: super::error::SimdjsonSchemaErrors,
+}
+
+impl Default for ValidationState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ValidationState {
@@ -101,6 +223,8 @@ impl ValidationState {
         ValidationState {
             errors: vec![],
             missing: vec![],
+            evaluated: hashbrown::HashSet::new(),
+            warnings: vec![],
         }
     }
 
@@ -111,6 +235,20 @@ impl ValidationState {
     pub fn append(&mut self, second: ValidationState) {
         self.errors.extend(second.errors);
         self.missing.extend(second.missing);
+        self.evaluated.extend(second.evaluated);
+        self.warnings.extend(second.warnings);
+    }
+
+    /// Appends the state of validating a property or an item, whose evaluated properties and
+    /// items are its own rather than of the validated value.
+    pub fn append_nested(&mut self, nested: ValidationState) {
+        self.errors.extend(nested.errors);
+        self.missing.extend(nested.missing);
+        self.warnings.extend(nested.warnings);
+    }
+
+    pub fn is_evaluated(&self, key: &str) -> bool {
+        self.evaluated.contains(key)
     }
 }
 
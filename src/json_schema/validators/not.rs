@@ -1,12 +1,12 @@
-use url;
 use value_trait::*;
 
 use super::error;
+use super::schema;
 use super::scope;
 
 #[allow(missing_copy_implementations)]
 pub struct Not {
-    pub url: url::Url,
+    pub url: schema::Link,
 }
 
 impl<V> super::Validator<V> for Not
@@ -28,19 +28,22 @@ where
         + std::marker::Send,
 {
     fn validate(&self, val: &V, path: &str, scope: &scope::Scope<V>) -> super::ValidationState {
-        let schema = scope.resolve(&self.url);
         let mut state = super::ValidationState::new();
 
-        if schema.is_some() {
-            if schema.unwrap().validate_in(val, path).is_valid() {
+        if let Some(schema) = scope.follow(&self.url) {
+            if schema.validate_in(val, path).is_valid() {
                 state.errors.push(Box::new(error::Not {
                     path: path.to_string(),
                 }))
             }
         } else {
-            state.missing.push(self.url.clone());
+            state.missing.push(self.url.url().clone());
         }
 
         state
     }
+
+    fn in_place(&self) -> Vec<&schema::Link> {
+        vec![&self.url]
+    }
 }
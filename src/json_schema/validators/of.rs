@@ -1,12 +1,12 @@
-use url;
 use value_trait::*;
 
 use super::error;
+use super::schema;
 use super::scope;
 
 #[allow(missing_copy_implementations)]
 pub struct AllOf {
-    pub schemes: Vec<url::Url>,
+    pub schemes: Vec<schema::Link>,
 }
 
 impl<V> super::Validator<V> for AllOf
@@ -31,22 +31,45 @@ where
         let mut state = super::ValidationState::new();
 
         for url in self.schemes.iter() {
-            let schema = scope.resolve(url);
-
-            if schema.is_some() {
-                state.append(schema.unwrap().validate_in(val, path))
+            if let Some(schema) = scope.follow(url) {
+                state.append(schema.validate_in(val, path))
             } else {
-                state.missing.push(url.clone())
+                state.missing.push(url.url().clone())
             }
         }
 
         state
     }
+
+    fn in_place(&self) -> Vec<&schema::Link> {
+        self.schemes.iter().collect()
+    }
+
+    fn normalize(
+        &self,
+        val: &mut V,
+        path: &str,
+        scope: &scope::Scope<V>,
+        normalization: &mut super::Normalization,
+    ) where
+        V: Mutable,
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::convert::From<String>,
+    {
+        for url in self.schemes.iter() {
+            if let Some(schema) = scope.follow(url) {
+                schema.normalize_in(val, path, normalization)
+            }
+        }
+    }
 }
 
 #[allow(missing_copy_implementations)]
 pub struct AnyOf {
-    pub schemes: Vec<url::Url>,
+    pub schemes: Vec<schema::Link>,
 }
 
 impl<V> super::Validator<V> for AnyOf
@@ -73,21 +96,21 @@ where
         let mut states = vec![];
         let mut valid = false;
         for url in self.schemes.iter() {
-            let schema = scope.resolve(url);
-
-            if schema.is_some() {
-                let current_state = schema.unwrap().validate_in(val, path);
+            if let Some(schema) = scope.follow(url) {
+                let current_state = schema.validate_in(val, path);
 
                 state.missing.extend(current_state.missing.clone());
 
+                // Every branch is evaluated, since all valid ones contribute annotations and warnings.
                 if current_state.is_valid() {
                     valid = true;
-                    break;
+                    state.evaluated.extend(current_state.evaluated);
+                    state.warnings.extend(current_state.warnings);
                 } else {
                     states.push(current_state)
                 }
             } else {
-                state.missing.push(url.clone())
+                state.missing.push(url.url().clone())
             }
         }
 
@@ -100,11 +123,15 @@ where
 
         state
     }
+
+    fn in_place(&self) -> Vec<&schema::Link> {
+        self.schemes.iter().collect()
+    }
 }
 
 #[allow(missing_copy_implementations)]
 pub struct OneOf {
-    pub schemes: Vec<url::Url>,
+    pub schemes: Vec<schema::Link>,
 }
 
 impl<V> super::Validator<V> for OneOf
@@ -131,20 +158,20 @@ where
         let mut states = vec![];
         let mut valid = 0;
         for url in self.schemes.iter() {
-            let schema = scope.resolve(url);
-
-            if schema.is_some() {
-                let current_state = schema.unwrap().validate_in(val, path);
+            if let Some(schema) = scope.follow(url) {
+                let current_state = schema.validate_in(val, path);
 
                 state.missing.extend(current_state.missing.clone());
 
                 if current_state.is_valid() {
                     valid += 1;
+                    state.evaluated.extend(current_state.evaluated);
+                    state.warnings.extend(current_state.warnings);
                 } else {
                     states.push(current_state)
                 }
             } else {
-                state.missing.push(url.clone())
+                state.missing.push(url.url().clone())
             }
         }
 
@@ -157,4 +184,8 @@ where
 
         state
     }
+
+    fn in_place(&self) -> Vec<&schema::Link> {
+        self.schemes.iter().collect()
+    }
 }
@@ -1,12 +1,13 @@
-use regex;
 use value_trait::*;
 
+use super::super::regex_engine;
 use super::error;
 use super::scope;
 
 #[allow(missing_copy_implementations)]
 pub struct Pattern {
-    pub regex: regex::Regex,
+    pub regex: Box<dyn regex_engine::Regex>,
+    pub input_length: Option<usize>,
 }
 
 impl<V> super::Validator<V> for Pattern
@@ -16,12 +17,24 @@ where
     fn validate(&self, val: &V, path: &str, _scope: &scope::Scope<V>) -> super::ValidationState {
         let string = nonstrict_process!(val.as_str(), path);
 
-        if self.regex.is_match(string) {
-            super::ValidationState::new()
-        } else {
-            val_error!(error::Pattern {
+        if let Some(limit) = self.input_length {
+            if string.chars().count() > limit {
+                return val_error!(error::PatternInputTooLong {
+                    path: path.to_string(),
+                    detail: format!("Strings longer than {} characters aren't matched", limit)
+                });
+            }
+        }
+
+        match self.regex.try_match(string) {
+            Ok(true) => super::ValidationState::new(),
+            Ok(false) => val_error!(error::Pattern {
                 path: path.to_string()
-            })
+            }),
+            Err(err) => val_error!(error::PatternTooComplex {
+                path: path.to_string(),
+                detail: err.to_string()
+            }),
         }
     }
 }
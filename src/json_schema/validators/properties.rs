@@ -1,21 +1,66 @@
-use regex;
-use url;
 use value_trait::*;
 
+use super::super::helpers;
+use super::super::regex_engine;
+use super::super::schema;
 use super::super::scope;
 use super::error;
 
 #[derive(Debug)]
 pub enum AdditionalKind {
     Boolean(bool),
-    Schema(url::Url),
+    Schema(schema::Link),
 }
 
 #[allow(missing_copy_implementations)]
 pub struct Properties {
-    pub properties: hashbrown::HashMap<String, url::Url>,
-    pub additional: AdditionalKind,
-    pub patterns: Vec<(regex::Regex, url::Url)>,
+    pub properties: hashbrown::HashMap<String, schema::Link>,
+    pub additional: Option<AdditionalKind>,
+    pub patterns: Vec<(Box<dyn regex_engine::Regex>, schema::Link)>,
+    pub input_length: Option<usize>,
+}
+
+impl Properties {
+    // The `patternProperties` subschemas a property name falls under, or the error of a name
+    // that can't be matched against them, which is neither matching nor additional
+    fn matching_patterns(
+        &self,
+        key: &str,
+        path: &str,
+    ) -> Result<Vec<&schema::Link>, Box<dyn error::SimdjsonSchemaError>> {
+        if self.patterns.is_empty() {
+            return Ok(vec![]);
+        }
+
+        if let Some(limit) = self
+            .input_length
+            .filter(|limit| key.chars().count() > *limit)
+        {
+            return Err(Box::new(error::PatternInputTooLong {
+                path: path.to_string(),
+                detail: format!(
+                    "Property names longer than {} characters aren't matched",
+                    limit
+                ),
+            }));
+        }
+
+        let mut urls = vec![];
+        for (regex, url) in self.patterns.iter() {
+            match regex.try_match(key) {
+                Ok(true) => urls.push(url),
+                Ok(false) => (),
+                Err(err) => {
+                    return Err(Box::new(error::PatternTooComplex {
+                        path: path.to_string(),
+                        detail: err.to_string(),
+                    }))
+                }
+            }
+        }
+
+        Ok(urls)
+    }
 }
 
 impl<V> super::Validator<V> for Properties
@@ -39,16 +84,20 @@ where
     fn validate(&self, val: &V, path: &str, scope: &scope::Scope<V>) -> super::ValidationState {
         let object = nonstrict_process!(val.as_object(), path);
         let mut state = super::ValidationState::new();
+        let tracks_evaluated = scope.tracks_evaluated();
 
         'main: for (key, value) in object.iter() {
+            let value_path = [path, helpers::escape_pointer_token(key.as_ref()).as_ref()].join("/");
+
             let is_property_passed = if self.properties.contains_key(key.as_ref()) {
                 let url = &self.properties[key.as_ref()];
-                let schema = scope.resolve(url);
-                if schema.is_some() {
-                    let value_path = [path, key.as_ref()].join("/");
-                    state.append(schema.unwrap().validate_in(value, value_path.as_ref()))
+                if let Some(schema) = scope.follow(url) {
+                    state.append_nested(schema.validate_in(value, value_path.as_ref()));
+                    if tracks_evaluated {
+                        state.evaluated.insert(key.to_string());
+                    }
                 } else {
-                    state.missing.push(url.clone())
+                    state.missing.push(url.url().clone())
                 }
 
                 true
@@ -56,17 +105,28 @@ where
                 false
             };
 
+            // Declared properties are validated all the same, the others aren't known to be
+            // additional
+            let urls = match self.matching_patterns(key.as_ref(), &value_path) {
+                Ok(urls) => urls,
+                Err(err) => {
+                    if !is_property_passed {
+                        state.errors.push(err);
+                    }
+                    continue 'main;
+                }
+            };
+
             let mut is_pattern_passed = false;
-            for &(ref regex, ref url) in self.patterns.iter() {
-                if regex.is_match(key.as_ref()) {
-                    let schema = scope.resolve(url);
-                    if schema.is_some() {
-                        let value_path = [path, key.as_ref()].join("/");
-                        state.append(schema.unwrap().validate_in(value, value_path.as_ref()));
-                        is_pattern_passed = true;
-                    } else {
-                        state.missing.push(url.clone())
+            for url in urls {
+                if let Some(schema) = scope.follow(url) {
+                    state.append_nested(schema.validate_in(value, value_path.as_ref()));
+                    if tracks_evaluated {
+                        state.evaluated.insert(key.to_string());
                     }
+                    is_pattern_passed = true;
+                } else {
+                    state.missing.push(url.url().clone())
                 }
             }
 
@@ -75,27 +135,170 @@ where
             }
 
             match self.additional {
-                AdditionalKind::Boolean(allowed) if !allowed => {
+                Some(AdditionalKind::Boolean(allowed)) if !allowed => {
                     state.errors.push(Box::new(error::Properties {
                         path: path.to_string(),
                         detail: "Additional properties are not allowed".to_string(),
                     }))
                 }
-                AdditionalKind::Schema(ref url) => {
-                    let schema = scope.resolve(url);
-
-                    if schema.is_some() {
-                        let value_path = [path, key.as_ref()].join("/");
-                        state.append(schema.unwrap().validate_in(value, value_path.as_ref()))
+                Some(AdditionalKind::Schema(ref url)) => {
+                    if let Some(schema) = scope.follow(url) {
+                        state.append_nested(schema.validate_in(value, value_path.as_ref()));
+                        if tracks_evaluated {
+                            state.evaluated.insert(key.to_string());
+                        }
                     } else {
-                        state.missing.push(url.clone())
+                        state.missing.push(url.url().clone())
                     }
                 }
                 // Additional are allowed here
-                _ => (),
+                Some(AdditionalKind::Boolean(_)) if tracks_evaluated => {
+                    state.evaluated.insert(key.to_string());
+                }
+                Some(AdditionalKind::Boolean(_)) | None => (),
             }
         }
 
         state
     }
+
+    fn normalize(
+        &self,
+        val: &mut V,
+        path: &str,
+        scope: &scope::Scope<V>,
+        normalization: &mut super::Normalization,
+    ) where
+        V: Mutable,
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::convert::From<String>,
+    {
+        if !val.is_object() {
+            return;
+        }
+
+        let remove = match normalization.options.remove_additional {
+            schema::RemoveAdditional::Never => false,
+            schema::RemoveAdditional::Forbidden => {
+                matches!(self.additional, Some(AdditionalKind::Boolean(false)))
+            }
+            schema::RemoveAdditional::All => true,
+        };
+
+        if remove {
+            let mut keys = val
+                .as_object()
+                .unwrap()
+                .iter()
+                .map(|(key, _)| key.as_ref().to_string())
+                .collect::<Vec<String>>();
+            keys.sort();
+
+            for key in keys {
+                let is_additional = !self.properties.contains_key(&key)
+                    && self
+                        .matching_patterns(&key, path)
+                        .is_ok_and(|urls| urls.is_empty());
+                if !is_additional {
+                    continue;
+                }
+
+                if val.remove(key.as_str()).is_ok() {
+                    normalization
+                        .removed
+                        .push([path, helpers::escape_pointer_token(&key).as_ref()].join("/"));
+                }
+            }
+        }
+
+        let mut names = self.properties.keys().collect::<Vec<&String>>();
+        names.sort();
+
+        for name in names {
+            let schema = scope.follow(&self.properties[name]);
+            if schema.is_none() {
+                continue;
+            }
+
+            let schema = schema.unwrap();
+            let value_path = [path, helpers::escape_pointer_token(name).as_ref()].join("/");
+
+            let strip_key = match normalization.options.strip {
+                Some(schema::Direction::Request) => Some("readOnly"),
+                Some(schema::Direction::Response) => Some("writeOnly"),
+                None => None,
+            };
+            let stripped = strip_key.is_some_and(|key| {
+                schema.source().get(key).and_then(|flag| flag.as_bool()) == Some(true)
+            });
+
+            if stripped {
+                if val.remove(name.as_str()).ok().flatten().is_some() {
+                    normalization.stripped.push(value_path);
+                }
+                continue;
+            }
+
+            if val.get(name.as_str()).is_none() {
+                if !normalization.options.defaults {
+                    continue;
+                }
+
+                let default = schema.source().get("default");
+                if default.is_none()
+                    || val
+                        .insert(name.clone(), V::from(default.unwrap().clone()))
+                        .is_err()
+                {
+                    continue;
+                }
+                normalization.populated.push(value_path.clone());
+            }
+
+            schema.normalize_in(
+                val.get_mut(name.as_str()).unwrap(),
+                value_path.as_ref(),
+                normalization,
+            );
+        }
+
+        // Values under `patternProperties` and `additionalProperties` only get the defaults of
+        // their own properties
+        let mut keys = val
+            .as_object()
+            .unwrap()
+            .iter()
+            .map(|(key, _)| key.as_ref().to_string())
+            .collect::<Vec<String>>();
+        keys.sort();
+
+        for key in keys {
+            // The validation reports the keys that can't be matched
+            let urls = self.matching_patterns(&key, path);
+            if urls.is_err() {
+                continue;
+            }
+
+            let mut urls = urls.unwrap();
+            if urls.is_empty() && !self.properties.contains_key(&key) {
+                if let Some(AdditionalKind::Schema(ref url)) = self.additional {
+                    urls.push(url);
+                }
+            }
+
+            let value_path = [path, helpers::escape_pointer_token(&key).as_ref()].join("/");
+            for url in urls {
+                if let Some(schema) = scope.follow(url) {
+                    schema.normalize_in(
+                        val.get_mut(key.as_str()).unwrap(),
+                        value_path.as_ref(),
+                        normalization,
+                    );
+                }
+            }
+        }
+    }
 }
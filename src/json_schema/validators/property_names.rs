@@ -1,11 +1,11 @@
-use url;
 use value_trait::*;
 
+use super::super::schema;
 use super::super::scope;
 
 #[allow(missing_copy_implementations)]
 pub struct PropertyNames {
-    pub url: url::Url,
+    pub url: schema::Link,
 }
 
 impl<V> super::Validator<V> for PropertyNames
@@ -29,19 +29,17 @@ where
     fn validate(&self, val: &V, path: &str, scope: &scope::Scope<V>) -> super::ValidationState {
         let object = nonstrict_process!(val.as_object(), path);
 
-        let schema = scope.resolve(&self.url);
+        let schema = scope.follow(&self.url);
         let mut state = super::ValidationState::new();
 
-        if schema.is_some() {
-            let schema = schema.unwrap();
+        if let Some(schema) = schema {
             for key in object.keys() {
                 let item_path = [path, ["[", key.as_ref(), "]"].join("").as_ref()].join("/");
-                // NOTE: Quite likely needing actual key thing here.
-                let v = object.get(key.as_ref()).unwrap();
-                state.append(schema.validate_in(v, item_path.as_ref()));
+                let name = V::from(simd_json::value::owned::Value::from(key.to_string()));
+                state.append_nested(schema.validate_in(&name, item_path.as_ref()));
             }
         } else {
-            state.missing.push(self.url.clone());
+            state.missing.push(self.url.url().clone());
         }
 
         state
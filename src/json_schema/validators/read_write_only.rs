@@ -0,0 +1,28 @@
+use value_trait::*;
+
+use super::error;
+use super::schema;
+use super::scope;
+
+#[allow(missing_copy_implementations)]
+pub struct ReadWriteOnly {
+    pub read_only: bool,
+    pub write_only: bool,
+}
+
+impl<V> super::Validator<V> for ReadWriteOnly
+where
+    V: Value,
+{
+    fn validate(&self, _val: &V, path: &str, _scope: &scope::Scope<V>) -> super::ValidationState {
+        match schema::direction() {
+            Some(schema::Direction::Request) if self.read_only => val_error!(error::ReadOnly {
+                path: path.to_string()
+            }),
+            Some(schema::Direction::Response) if self.write_only => val_error!(error::WriteOnly {
+                path: path.to_string()
+            }),
+            _ => super::ValidationState::new(),
+        }
+    }
+}
@@ -1,9 +1,11 @@
 use value_trait::*;
 
+use super::helpers;
+use super::schema;
 use super::scope;
 
 pub struct Ref {
-    pub url: url::Url,
+    pub url: schema::Link,
 }
 
 impl<V> super::Validator<V> for Ref
@@ -22,14 +24,258 @@ where
         + std::string::ToString,
 {
     fn validate(&self, val: &V, path: &str, scope: &scope::Scope<V>) -> super::ValidationState {
-        let schema = scope.resolve(&self.url);
+        if let Some(schema) = scope.follow(&self.url) {
+            schema.validate_in(val, path)
+        } else {
+            let mut state = super::ValidationState::new();
+            state.missing.push(self.url.url().clone());
+            state
+        }
+    }
+
+    fn in_place(&self) -> Vec<&schema::Link> {
+        vec![&self.url]
+    }
+
+    fn references(&self) -> Vec<&url::Url> {
+        vec![self.url.url()]
+    }
+
+    fn normalize(
+        &self,
+        val: &mut V,
+        path: &str,
+        scope: &scope::Scope<V>,
+        normalization: &mut super::Normalization,
+    ) where
+        V: Mutable,
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::convert::From<String>,
+    {
+        if let Some(schema) = scope.follow(&self.url) {
+            schema.normalize_in(val, path, normalization)
+        }
+    }
+}
+
+pub struct DynamicRef {
+    pub url: schema::Link,
+    // The plain-name fragment of `url`, if it names a `$dynamicAnchor`
+    pub anchor: Option<String>,
+}
+
+impl DynamicRef {
+    // The schema the reference resolves to in the dynamic scope of the running validation
+    fn target<'scope, V>(
+        &self,
+        scope: &'scope scope::Scope<V>,
+    ) -> Option<schema::ScopedSchema<'scope, 'scope, V>>
+    where
+        V: Value
+            + std::clone::Clone
+            + std::convert::From<simd_json::value::owned::Value>
+            + std::fmt::Display
+            + std::marker::Sync
+            + std::marker::Send
+            + std::cmp::PartialEq,
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + std::convert::AsRef<str>
+            + std::fmt::Debug
+            + std::string::ToString,
+    {
+        let schema = scope.follow(&self.url)?;
+        let anchor = self.anchor.as_deref();
+
+        // Only a reference whose static target is a `$dynamicAnchor` is dynamic
+        if anchor.is_none()
+            || schema
+                .source()
+                .get("$dynamicAnchor")
+                .and_then(|anchor| anchor.as_str())
+                != anchor
+        {
+            return Some(schema);
+        }
+
+        let dynamic = schema::with_dynamic_scope(|resources| {
+            resources.iter().find_map(|base| {
+                scope
+                    .resolve(&helpers::anchor_url(base, anchor.unwrap()))
+                    .filter(|schema| {
+                        schema
+                            .source()
+                            .get("$dynamicAnchor")
+                            .and_then(|anchor| anchor.as_str())
+                            == anchor
+                    })
+            })
+        });
+
+        Some(dynamic.unwrap_or(schema))
+    }
+}
+
+impl<V> super::Validator<V> for DynamicRef
+where
+    V: Value
+        + std::clone::Clone
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::fmt::Display
+        + std::marker::Sync
+        + std::marker::Send
+        + std::cmp::PartialEq,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::hash::Hash
+        + std::convert::AsRef<str>
+        + std::fmt::Debug
+        + std::string::ToString,
+{
+    fn validate(&self, val: &V, path: &str, scope: &scope::Scope<V>) -> super::ValidationState {
+        if let Some(schema) = self.target(scope) {
+            schema.validate_in(val, path)
+        } else {
+            let mut state = super::ValidationState::new();
+            state.missing.push(self.url.url().clone());
+            state
+        }
+    }
+
+    fn in_place(&self) -> Vec<&schema::Link> {
+        vec![&self.url]
+    }
+
+    fn is_dynamic(&self) -> bool {
+        true
+    }
+
+    fn references(&self) -> Vec<&url::Url> {
+        vec![self.url.url()]
+    }
+
+    fn normalize(
+        &self,
+        val: &mut V,
+        path: &str,
+        scope: &scope::Scope<V>,
+        normalization: &mut super::Normalization,
+    ) where
+        V: Mutable,
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::convert::From<String>,
+    {
+        if let Some(schema) = self.target(scope) {
+            schema.normalize_in(val, path, normalization)
+        }
+    }
+}
 
-        if schema.is_some() {
-            schema.unwrap().validate_in(val, path)
+pub struct RecursiveRef {
+    pub url: schema::Link,
+}
+
+impl RecursiveRef {
+    // The schema the reference resolves to in the dynamic scope of the running validation
+    fn target<'scope, V>(
+        &self,
+        scope: &'scope scope::Scope<V>,
+    ) -> Option<schema::ScopedSchema<'scope, 'scope, V>>
+    where
+        V: Value
+            + std::clone::Clone
+            + std::convert::From<simd_json::value::owned::Value>
+            + std::fmt::Display
+            + std::marker::Sync
+            + std::marker::Send
+            + std::cmp::PartialEq,
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + std::convert::AsRef<str>
+            + std::fmt::Debug
+            + std::string::ToString,
+    {
+        let schema = scope.follow(&self.url)?;
+
+        if schema
+            .source()
+            .get("$recursiveAnchor")
+            .and_then(|anchor| anchor.as_bool())
+            != Some(true)
+        {
+            return Some(schema);
+        }
+
+        let recursive = schema::with_dynamic_scope(|resources| {
+            resources.iter().find_map(|base| {
+                scope.resolve(base).filter(|schema| {
+                    schema
+                        .source()
+                        .get("$recursiveAnchor")
+                        .and_then(|anchor| anchor.as_bool())
+                        == Some(true)
+                })
+            })
+        });
+
+        Some(recursive.unwrap_or(schema))
+    }
+}
+
+impl<V> super::Validator<V> for RecursiveRef
+where
+    V: Value
+        + std::clone::Clone
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::fmt::Display
+        + std::marker::Sync
+        + std::marker::Send
+        + std::cmp::PartialEq,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::hash::Hash
+        + std::convert::AsRef<str>
+        + std::fmt::Debug
+        + std::string::ToString,
+{
+    fn validate(&self, val: &V, path: &str, scope: &scope::Scope<V>) -> super::ValidationState {
+        if let Some(schema) = self.target(scope) {
+            schema.validate_in(val, path)
         } else {
             let mut state = super::ValidationState::new();
-            state.missing.push(self.url.clone());
+            state.missing.push(self.url.url().clone());
             state
         }
     }
+
+    fn in_place(&self) -> Vec<&schema::Link> {
+        vec![&self.url]
+    }
+
+    fn is_dynamic(&self) -> bool {
+        true
+    }
+
+    fn normalize(
+        &self,
+        val: &mut V,
+        path: &str,
+        scope: &scope::Scope<V>,
+        normalization: &mut super::Normalization,
+    ) where
+        V: Mutable,
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::convert::From<String>,
+    {
+        if let Some(schema) = self.target(scope) {
+            schema.normalize_in(val, path, normalization)
+        }
+    }
 }
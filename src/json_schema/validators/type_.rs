@@ -1,3 +1,6 @@
+use serde_json;
+use simd_json::value::owned::Value as OwnedValue;
+use std::convert::TryFrom;
 use value_trait::*;
 
 use super::error;
@@ -24,13 +27,98 @@ fn check_type<V: Value>(val: &V, ty: pt::PrimitiveType) -> bool {
             let is_integer_float = val.is_f64() && val.as_f64().unwrap().fract() == 0.0;
             is_true_integer || is_integer_float
         }
-        pt::PrimitiveType::Number => (val.is_f64() || val.is_i64() || val.is_u64()),
+        pt::PrimitiveType::Number => val.is_f64() || val.is_i64() || val.is_u64(),
         pt::PrimitiveType::Null => val.is_null(),
         pt::PrimitiveType::Object => val.is_object(),
         pt::PrimitiveType::String => val.is_str(),
     }
 }
 
+fn primitive_type<V: Value>(val: &V) -> pt::PrimitiveType {
+    if val.is_array() {
+        pt::PrimitiveType::Array
+    } else if val.is_bool() {
+        pt::PrimitiveType::Boolean
+    } else if val.is_i64() || val.is_u64() {
+        pt::PrimitiveType::Integer
+    } else if val.is_f64() {
+        pt::PrimitiveType::Number
+    } else if val.is_null() {
+        pt::PrimitiveType::Null
+    } else if val.is_object() {
+        pt::PrimitiveType::Object
+    } else {
+        pt::PrimitiveType::String
+    }
+}
+
+// A string holding exactly a JSON number, without surrounding whitespace or a leading `+`
+fn parse_number(string: &str) -> Option<OwnedValue> {
+    if string.trim() != string {
+        return None;
+    }
+
+    serde_json::from_str::<serde_json::Number>(string)
+        .ok()
+        .and_then(|number| OwnedValue::try_from(serde_json::Value::Number(number)).ok())
+}
+
+// `val` converted to `ty` as ajv's `coerceTypes` does, if that is lossless. Arrays are built by
+// the caller, which can move `val` into one.
+fn coerce_value<V>(val: &V, ty: pt::PrimitiveType) -> Option<OwnedValue>
+where
+    V: Value + std::fmt::Display,
+{
+    match ty {
+        pt::PrimitiveType::Integer | pt::PrimitiveType::Number => {
+            let number = if val.is_str() {
+                parse_number(val.as_str().unwrap())?
+            } else if val.is_bool() {
+                OwnedValue::from(if val.as_bool().unwrap() { 1 } else { 0 })
+            } else if val.is_null() {
+                OwnedValue::from(0)
+            } else {
+                return None;
+            };
+
+            if check_type(&number, ty) {
+                Some(number)
+            } else {
+                None
+            }
+        }
+        pt::PrimitiveType::String => {
+            if val.is_null() {
+                Some(OwnedValue::from(""))
+            } else if val.is_bool() || val.is_i64() || val.is_u64() || val.is_f64() {
+                Some(OwnedValue::from(val.to_string()))
+            } else {
+                None
+            }
+        }
+        pt::PrimitiveType::Boolean => match val.as_str() {
+            Some("true") => Some(OwnedValue::from(true)),
+            Some("false") => Some(OwnedValue::from(false)),
+            Some(_) => None,
+            None if val.is_null() => Some(OwnedValue::from(false)),
+            None if val.cast_f64() == Some(1.0) => Some(OwnedValue::from(true)),
+            None if val.cast_f64() == Some(0.0) => Some(OwnedValue::from(false)),
+            None => None,
+        },
+        pt::PrimitiveType::Null => {
+            let is_empty = val.as_str() == Some("")
+                || val.as_bool() == Some(false)
+                || val.cast_f64() == Some(0.0);
+            if is_empty {
+                Some(OwnedValue::from(()))
+            } else {
+                None
+            }
+        }
+        pt::PrimitiveType::Array | pt::PrimitiveType::Object => None,
+    }
+}
+
 impl<V> super::Validator<V> for Type
 where
     V: Value
@@ -84,4 +172,49 @@ where
 
         state
     }
+
+    fn coerce(&self, val: &mut V, path: &str, coerced: &mut Vec<super::Coercion>)
+    where
+        V: Mutable,
+    {
+        let types = match self.item {
+            TypeKind::Single(ref ty) => std::slice::from_ref(ty),
+            TypeKind::Set(ref set) => set.as_slice(),
+        };
+
+        if types.iter().any(|ty| check_type(val, *ty)) {
+            return;
+        }
+
+        let from = primitive_type(val);
+
+        // The first type of the set the value converts to wins
+        for ty in types.iter() {
+            if *ty == pt::PrimitiveType::Array {
+                if val.is_array() || val.is_object() {
+                    continue;
+                }
+
+                let item = std::mem::replace(val, V::from(OwnedValue::Array(vec![])));
+                if val.push(item).is_ok() {
+                    coerced.push(super::Coercion {
+                        path: path.to_string(),
+                        from,
+                        to: *ty,
+                    });
+                }
+                return;
+            }
+
+            if let Some(value) = coerce_value(val, *ty) {
+                *val = V::from(value);
+                coerced.push(super::Coercion {
+                    path: path.to_string(),
+                    from,
+                    to: *ty,
+                });
+                return;
+            }
+        }
+    }
 }
@@ -0,0 +1,175 @@
+use value_trait::*;
+
+use super::super::helpers;
+use super::error;
+use super::schema;
+use super::scope;
+
+#[derive(Debug)]
+pub enum UnevaluatedKind {
+    Boolean(bool),
+    Schema(schema::Link),
+}
+
+impl UnevaluatedKind {
+    fn validate_in<V>(
+        &self,
+        val: &V,
+        path: &str,
+        scope: &scope::Scope<V>,
+        state: &mut super::ValidationState,
+    ) -> bool
+    where
+        V: Value
+            + std::clone::Clone
+            + std::convert::From<simd_json::value::owned::Value>
+            + std::fmt::Display,
+        <V as Value>::Key: std::borrow::Borrow<str>
+            + std::hash::Hash
+            + Eq
+            + std::convert::AsRef<str>
+            + std::fmt::Debug
+            + std::string::ToString,
+    {
+        match self {
+            UnevaluatedKind::Boolean(allowed) => *allowed,
+            UnevaluatedKind::Schema(url) => {
+                if let Some(schema) = scope.follow(url) {
+                    state.append_nested(schema.validate_in(val, path));
+                } else {
+                    state.missing.push(url.url().clone())
+                }
+                true
+            }
+        }
+    }
+}
+
+#[allow(missing_copy_implementations)]
+pub struct UnevaluatedProperties {
+    pub kind: UnevaluatedKind,
+}
+
+impl<V> super::Validator<V> for UnevaluatedProperties
+where
+    V: Value
+        + std::clone::Clone
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::fmt::Display
+        + std::marker::Sync
+        + std::marker::Send
+        + std::cmp::PartialEq,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::hash::Hash
+        + Eq
+        + std::convert::AsRef<str>
+        + std::fmt::Debug
+        + std::string::ToString
+        + std::marker::Sync
+        + std::marker::Send,
+{
+    fn validate(&self, val: &V, path: &str, scope: &scope::Scope<V>) -> super::ValidationState {
+        self.validate_evaluated(val, path, scope, &super::ValidationState::new())
+    }
+
+    fn is_deferred(&self) -> bool {
+        true
+    }
+
+    fn validate_evaluated(
+        &self,
+        val: &V,
+        path: &str,
+        scope: &scope::Scope<V>,
+        evaluated: &super::ValidationState,
+    ) -> super::ValidationState {
+        let object = nonstrict_process!(val.as_object(), path);
+        let mut state = super::ValidationState::new();
+
+        for (key, value) in object.iter() {
+            if evaluated.is_evaluated(key.as_ref()) {
+                continue;
+            }
+
+            let value_path = [path, helpers::escape_pointer_token(key.as_ref()).as_ref()].join("/");
+            if self
+                .kind
+                .validate_in(value, value_path.as_ref(), scope, &mut state)
+            {
+                state.evaluated.insert(key.to_string());
+            } else {
+                state.errors.push(Box::new(error::Properties {
+                    path: path.to_string(),
+                    detail: "Unevaluated properties are not allowed".to_string(),
+                }))
+            }
+        }
+
+        state
+    }
+}
+
+#[allow(missing_copy_implementations)]
+pub struct UnevaluatedItems {
+    pub kind: UnevaluatedKind,
+}
+
+impl<V> super::Validator<V> for UnevaluatedItems
+where
+    V: Value
+        + std::clone::Clone
+        + std::convert::From<simd_json::value::owned::Value>
+        + std::fmt::Display
+        + std::marker::Sync
+        + std::marker::Send
+        + std::cmp::PartialEq,
+    <V as Value>::Key: std::borrow::Borrow<str>
+        + std::hash::Hash
+        + Eq
+        + std::convert::AsRef<str>
+        + std::fmt::Debug
+        + std::string::ToString
+        + std::marker::Sync
+        + std::marker::Send,
+{
+    fn validate(&self, val: &V, path: &str, scope: &scope::Scope<V>) -> super::ValidationState {
+        self.validate_evaluated(val, path, scope, &super::ValidationState::new())
+    }
+
+    fn is_deferred(&self) -> bool {
+        true
+    }
+
+    fn validate_evaluated(
+        &self,
+        val: &V,
+        path: &str,
+        scope: &scope::Scope<V>,
+        evaluated: &super::ValidationState,
+    ) -> super::ValidationState {
+        let array = nonstrict_process!(val.as_array(), path);
+        let mut state = super::ValidationState::new();
+
+        for (idx, item) in array.iter().enumerate() {
+            let index = idx.to_string();
+            if evaluated.is_evaluated(&index) {
+                continue;
+            }
+
+            let item_path = [path, index.as_ref()].join("/");
+            if self
+                .kind
+                .validate_in(item, item_path.as_ref(), scope, &mut state)
+            {
+                state.evaluated.insert(index);
+            } else {
+                state.errors.push(Box::new(error::Items {
+                    path: path.to_string(),
+                    detail: "Unevaluated items are not allowed".to_string(),
+                }))
+            }
+        }
+
+        state
+    }
+}